@@ -0,0 +1,57 @@
+//! Times background pattern generation for a 4K view at small gaps.
+//!
+//! Run with `cargo bench --bench background`.
+
+use gpui::*;
+use gpuiflow::components::background::{
+    BackgroundProps, BackgroundVariant, PatternQuads, pattern_quads,
+};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const ITERATIONS: u32 = 50;
+/// Generating a frame's worth of quads should stay well inside a 60 fps frame.
+const BUDGET: Duration = Duration::from_millis(4);
+/// Two cross-faded grid levels, each capped by `pattern_quads`.
+const MAX_QUADS: usize = 50_000;
+
+fn main() {
+    let view = size(3840.0, 2160.0);
+    let mut over_budget = false;
+
+    for variant in [
+        BackgroundVariant::Dots,
+        BackgroundVariant::Lines,
+        BackgroundVariant::Cross,
+    ] {
+        for gap in [5.0, 10.0, 20.0] {
+            for merge_levels in [true, false] {
+                let props = BackgroundProps {
+                    variant: variant.clone(),
+                    gap,
+                    merge_levels,
+                    ..BackgroundProps::default()
+                };
+                let (per_frame, quads) = time(view, &props, 1.0);
+                over_budget |= per_frame > BUDGET || quads > MAX_QUADS;
+                println!(
+                    "{variant:?} gap={gap} merge_levels={merge_levels}: {quads} quads, {per_frame:?}/frame"
+                );
+            }
+        }
+    }
+
+    if over_budget {
+        eprintln!("background generation exceeded {BUDGET:?} or {MAX_QUADS} quads per frame");
+        std::process::exit(1);
+    }
+}
+
+fn time(view: Size<f32>, props: &BackgroundProps, zoom: f32) -> (Duration, usize) {
+    let mut pattern = PatternQuads::default();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        pattern = black_box(pattern_quads(black_box(view), props, zoom));
+    }
+    (start.elapsed() / ITERATIONS, pattern.quads.len())
+}
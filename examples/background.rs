@@ -1,10 +1,11 @@
 use gpui::*;
-use gpuiflow::components::background::{BackgroundProps, BackgroundVariant};
-use gpuiflow::{Edge, GraphView, Handle, HandleType, Node, Position};
+use gpuiflow::components::background::{Background, BackgroundProps, BackgroundVariant};
+use gpuiflow::{Edge, Graph, GraphView, Handle, HandleType, Node, Position};
 
 struct BackgroundExample {
     graph: Entity<GraphView<String>>,
     background: BackgroundProps,
+    major_lines: bool,
 }
 
 impl BackgroundExample {
@@ -24,8 +25,11 @@ impl BackgroundExample {
                     Handle::new("out", HandleType::Source, Position::Right),
                 ]);
 
-            let node3 = Node::new("Output".to_string(), Point::new(350.0, 250.0))
-                .with_handles(vec![Handle::new("in", HandleType::Target, Position::Left)]);
+            let node3 =
+                Node::new("Output".to_string(), Point::new(350.0, 250.0)).with_handles(vec![
+                    Handle::new("in", HandleType::Target, Position::Left),
+                    Handle::new("out", HandleType::Source, Position::Right),
+                ]);
 
             let node4 =
                 Node::new("Result".to_string(), Point::new(600.0, 175.0)).with_handles(vec![
@@ -37,16 +41,18 @@ impl BackgroundExample {
             let edge1 = Edge::new(node1.id, node2.id).with_handles("out", "in");
             let edge2 = Edge::new(node1.id, node3.id).with_handles("out", "in");
             let edge3 = Edge::new(node2.id, node4.id).with_handles("out", "in1");
-            let edge4 = Edge::new(node3.id, node4.id).with_handles("in", "in2");
-
-            view.add_node(node1);
-            view.add_node(node2);
-            view.add_node(node3);
-            view.add_node(node4);
-            view.add_edge(edge1);
-            view.add_edge(edge2);
-            view.add_edge(edge3);
-            view.add_edge(edge4);
+            let edge4 = Edge::new(node3.id, node4.id).with_handles("out", "in2");
+
+            let mut graph = Graph::new();
+            graph.add_node(node1);
+            graph.add_node(node2);
+            graph.add_node(node3);
+            graph.add_node(node4);
+            graph.add_edge(edge1);
+            graph.add_edge(edge2);
+            graph.add_edge(edge3);
+            graph.add_edge(edge4);
+            view.set_graph(graph, cx);
 
             view
         });
@@ -54,13 +60,23 @@ impl BackgroundExample {
         Self {
             graph,
             background: BackgroundProps::default(),
+            major_lines: false,
         }
     }
 
     fn update_background(&mut self, cx: &mut Context<Self>) {
-        let props = self.background;
+        let mut background = Background::from(self.background.clone());
+        if self.major_lines {
+            // Major lines every five gaps, stacked on top of the selected pattern
+            background = background.with_layer(BackgroundProps {
+                variant: BackgroundVariant::Lines,
+                gap: self.background.gap * 5.0,
+                color: rgb(0x444444).into(),
+                ..BackgroundProps::default()
+            });
+        }
         self.graph.update(cx, |view, cx| {
-            view.set_background(props, cx);
+            view.set_background(background, cx);
         });
         cx.notify();
     }
@@ -92,7 +108,7 @@ impl BackgroundExample {
             .on_mouse_down(
                 MouseButton::Left,
                 cx.listener(move |this, _, _window, cx| {
-                    this.background.variant = variant;
+                    this.background.variant = variant.clone();
                     this.update_background(cx);
                 }),
             )
@@ -177,6 +193,28 @@ impl Render for BackgroundExample {
                         .child(self.render_variant_button(BackgroundVariant::Lines, "Lines", cx))
                         .child(self.render_variant_button(BackgroundVariant::Cross, "Cross", cx)),
                 )
+                // Major Lines Toggle
+                .child(
+                    div()
+                        .flex()
+                        .justify_center()
+                        .bg(if self.major_lines {
+                            rgb(0x4a90e2)
+                        } else {
+                            rgb(0x444444)
+                        })
+                        .py(px(4.0))
+                        .rounded_md()
+                        .cursor_pointer()
+                        .child("Major lines")
+                        .on_mouse_down(
+                            MouseButton::Left,
+                            cx.listener(|this, _, _window, cx| {
+                                this.major_lines = !this.major_lines;
+                                this.update_background(cx);
+                            }),
+                        ),
+                )
                 // Gap Slider
                 .child(self.render_slider(
                     "Gap",
@@ -1,5 +1,5 @@
 use gpui::*;
-use gpuiflow::{Edge, GraphView, Node};
+use gpuiflow::{Edge, Graph, GraphView, Node};
 
 fn main() {
     gpui::Application::new().run(|cx| {
@@ -10,9 +10,11 @@ fn main() {
                 let node2 = Node::new((), Point::new(400.0, 300.0));
                 let edge = Edge::new(node1.id, node2.id);
 
-                view.add_node(node1);
-                view.add_node(node2);
-                view.add_edge(edge);
+                let mut graph = Graph::new();
+                graph.add_node(node1);
+                graph.add_node(node2);
+                graph.add_edge(edge);
+                view.set_graph(graph, cx);
                 view
             })
         })
@@ -1,5 +1,5 @@
 use gpui::*;
-use gpuiflow::{Edge, GraphView, Node};
+use gpuiflow::{Edge, Graph, GraphView, Node};
 
 fn main() {
     gpui::Application::new().run(|cx| {
@@ -8,14 +8,20 @@ fn main() {
                 let mut view = GraphView::<()>::new(cx);
 
                 // Register a custom node type "custom"
-                view.register_node_type("custom", |_node, _cx| {
+                view.register_node_type("custom", |_node, ctx, _cx| {
                     div()
                         .size_full()
                         .bg(rgb(0x505080)) // Different background color
-                        .border_2()
-                        .border_color(rgb(0x8080ff))
+                        .border(ctx.px(2.0))
+                        .border_color(if ctx.selected {
+                            rgb(0xffffff)
+                        } else {
+                            rgb(0x8080ff)
+                        })
                         .rounded_xl()
                         .shadow_lg()
+                        .px_6()
+                        .py_4()
                         .flex()
                         .items_center()
                         .justify_center()
@@ -32,9 +38,11 @@ fn main() {
                 let node2 = Node::new((), Point::new(400.0, 300.0)).with_type("custom");
                 let edge = Edge::new(node1.id, node2.id);
 
-                view.add_node(node1);
-                view.add_node(node2);
-                view.add_edge(edge);
+                let mut graph = Graph::new();
+                graph.add_node(node1);
+                graph.add_node(node2);
+                graph.add_edge(edge);
+                view.set_graph(graph, cx);
                 view
             })
         })
@@ -1,6 +1,6 @@
 use gpui::*;
 use gpuiflow::types::position::Position;
-use gpuiflow::{Edge, GraphView, Handle, HandleType, Node};
+use gpuiflow::{Edge, Graph, GraphView, Handle, HandleType, Node};
 
 fn main() {
     gpui::Application::new().run(|cx| {
@@ -27,11 +27,13 @@ fn main() {
 
                 let edge2 = Edge::new(node1.id, node3.id).with_handles("bottom", "top");
 
-                view.add_node(node1);
-                view.add_node(node2);
-                view.add_node(node3);
-                view.add_edge(edge1);
-                view.add_edge(edge2);
+                let mut graph = Graph::new();
+                graph.add_node(node1);
+                graph.add_node(node2);
+                graph.add_node(node3);
+                graph.add_edge(edge1);
+                graph.add_edge(edge2);
+                view.set_graph(graph, cx);
                 view
             })
         })
@@ -0,0 +1,259 @@
+use crate::graph::{Edge, Graph, Node};
+use gpui::{Point, Size};
+use uuid::Uuid;
+
+/// A change to a node, emitted by `GraphView` before it is applied.
+///
+/// Mirrors React Flow's `NodeChange`: hosts can inspect, rewrite or drop
+/// changes in `GraphView::on_nodes_change` before they reach the graph.
+#[derive(Clone, Debug)]
+pub enum NodeChange<D> {
+    Position {
+        id: Uuid,
+        position: Point<f32>,
+        /// `true` while the node is being dragged, `false` for the final position.
+        dragging: bool,
+    },
+    Dimensions {
+        id: Uuid,
+        size: Size<f32>,
+    },
+    Select {
+        id: Uuid,
+        selected: bool,
+    },
+    /// Replaces the node's data, leaving the rest of the node as it is.
+    Data {
+        id: Uuid,
+        data: D,
+    },
+    Remove {
+        id: Uuid,
+    },
+    Add {
+        node: Node<D>,
+    },
+    /// Adds a node at `index` in the z-order, e.g. when undoing a removal.
+    Insert {
+        index: usize,
+        node: Node<D>,
+    },
+    Replace {
+        node: Node<D>,
+    },
+}
+
+impl<D> NodeChange<D> {
+    /// Id of the node this change applies to.
+    pub fn id(&self) -> Uuid {
+        match self {
+            NodeChange::Position { id, .. }
+            | NodeChange::Dimensions { id, .. }
+            | NodeChange::Select { id, .. }
+            | NodeChange::Data { id, .. }
+            | NodeChange::Remove { id } => *id,
+            NodeChange::Add { node }
+            | NodeChange::Insert { node, .. }
+            | NodeChange::Replace { node } => node.id,
+        }
+    }
+}
+
+/// A change to an edge, emitted by `GraphView` before it is applied.
+#[derive(Clone, Debug)]
+pub enum EdgeChange {
+    Select {
+        id: Uuid,
+        selected: bool,
+    },
+    Remove {
+        id: Uuid,
+    },
+    Add {
+        edge: Edge,
+    },
+    /// Adds an edge at `index` in the draw order, e.g. when undoing a removal.
+    Insert {
+        index: usize,
+        edge: Edge,
+    },
+    Replace {
+        edge: Edge,
+    },
+}
+
+impl EdgeChange {
+    /// Id of the edge this change applies to.
+    pub fn id(&self) -> Uuid {
+        match self {
+            EdgeChange::Select { id, .. } | EdgeChange::Remove { id } => *id,
+            EdgeChange::Add { edge }
+            | EdgeChange::Insert { edge, .. }
+            | EdgeChange::Replace { edge } => edge.id,
+        }
+    }
+}
+
+impl<D> Graph<D> {
+    /// Applies node changes in order. Changes referring to unknown nodes are ignored.
+    pub fn apply_node_changes(&mut self, changes: impl IntoIterator<Item = NodeChange<D>>) {
+        for change in changes {
+            match change {
+                NodeChange::Position { id, position, .. } => {
+                    if let Some(node) = self.get_node_mut(id) {
+                        node.position = position;
+                    }
+                }
+                NodeChange::Dimensions { id, size } => {
+                    if let Some(node) = self.get_node_mut(id) {
+                        node.measured = Some(size);
+                    }
+                }
+                NodeChange::Select { id, selected } => {
+                    if let Some(node) = self.get_node_mut(id) {
+                        node.selected = selected;
+                    }
+                }
+                NodeChange::Data { id, data } => {
+                    if let Some(node) = self.get_node_mut(id) {
+                        node.data = data;
+                    }
+                }
+                NodeChange::Remove { id } => {
+                    self.remove_node(id);
+                }
+                NodeChange::Add { node } => {
+                    self.add_node(node);
+                }
+                NodeChange::Insert { index, node } => {
+                    self.insert_node(index, node);
+                }
+                NodeChange::Replace { node } => {
+                    if self.contains_node(node.id) {
+                        self.add_node(node);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Applies edge changes in order. Changes referring to unknown edges are ignored.
+    pub fn apply_edge_changes(&mut self, changes: impl IntoIterator<Item = EdgeChange>) {
+        for change in changes {
+            match change {
+                EdgeChange::Select { id, selected } => {
+                    if let Some(edge) = self.get_edge_mut(id) {
+                        edge.selected = selected;
+                    }
+                }
+                EdgeChange::Remove { id } => {
+                    self.remove_edge(id);
+                }
+                EdgeChange::Add { edge } => {
+                    self.add_edge(edge);
+                }
+                EdgeChange::Insert { index, edge } => {
+                    self.insert_edge(index, edge);
+                }
+                EdgeChange::Replace { edge } => {
+                    if self.contains_edge(edge.id) {
+                        self.add_edge(edge);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::point;
+
+    /// Three nodes in z-order with edges `a -> b` and `b -> c`.
+    fn chain() -> (Graph<u32>, [Uuid; 3], [Uuid; 2]) {
+        let mut graph = Graph::new();
+        let nodes = [0, 1, 2].map(|i| Node::new(i, point(i as f32 * 200.0, 0.0)));
+        let node_ids = nodes.each_ref().map(|node| node.id);
+        for node in nodes {
+            graph.add_node(node);
+        }
+        let edges = [
+            Edge::new(node_ids[0], node_ids[1]),
+            Edge::new(node_ids[1], node_ids[2]),
+        ];
+        let edge_ids = edges.each_ref().map(|edge| edge.id);
+        for edge in edges {
+            graph.add_edge(edge);
+        }
+        (graph, node_ids, edge_ids)
+    }
+
+    fn node_ids(graph: &Graph<u32>) -> Vec<Uuid> {
+        graph.nodes().iter().map(|node| node.id).collect()
+    }
+
+    fn edge_ids(graph: &Graph<u32>) -> Vec<Uuid> {
+        graph.edges().iter().map(|edge| edge.id).collect()
+    }
+
+    #[test]
+    fn replace_ignores_unknown_ids() {
+        let (mut graph, nodes, edges) = chain();
+        let (a, b) = (nodes[0], nodes[1]);
+        graph.apply_node_changes([NodeChange::Replace {
+            node: Node::new(7, point(0.0, 0.0)),
+        }]);
+        graph.apply_edge_changes([EdgeChange::Replace {
+            edge: Edge::new(b, a),
+        }]);
+        assert_eq!(node_ids(&graph), nodes);
+        assert_eq!(edge_ids(&graph), edges);
+
+        let mut replacement = graph.get_node(b).unwrap().clone();
+        replacement.data = 7;
+        graph.apply_node_changes([NodeChange::Replace { node: replacement }]);
+        assert_eq!(node_ids(&graph), nodes);
+        assert_eq!(graph.get_node(b).unwrap().data, 7);
+    }
+
+    #[test]
+    fn insert_keeps_z_order() {
+        let (mut graph, nodes, edges) = chain();
+        let node = graph.get_node(nodes[1]).unwrap().clone();
+        let [first, second] = edges.map(|id| graph.get_edge(id).unwrap().clone());
+        graph.apply_node_changes([NodeChange::Remove { id: nodes[1] }]);
+        assert_eq!(node_ids(&graph), [nodes[0], nodes[2]]);
+        assert!(graph.edges().is_empty());
+
+        graph.apply_node_changes([NodeChange::Insert { index: 1, node }]);
+        assert_eq!(node_ids(&graph), nodes);
+        assert_eq!(graph.node_index(nodes[2]), Some(2));
+
+        graph.apply_edge_changes([
+            EdgeChange::Insert {
+                index: 0,
+                edge: second,
+            },
+            EdgeChange::Insert {
+                index: 0,
+                edge: first,
+            },
+        ]);
+        assert_eq!(edge_ids(&graph), edges);
+        assert_eq!(graph.edge_index(edges[1]), Some(1));
+    }
+
+    #[test]
+    fn remove_cascades_to_edges() {
+        let (mut graph, nodes, edges) = chain();
+        graph.apply_node_changes([NodeChange::Remove { id: nodes[1] }]);
+        assert!(!graph.contains_node(nodes[1]));
+        assert!(edges.iter().all(|&id| !graph.contains_edge(id)));
+
+        let (mut graph, _, edges) = chain();
+        graph.apply_edge_changes([EdgeChange::Remove { id: edges[0] }]);
+        assert_eq!(edge_ids(&graph), [edges[1]]);
+        assert_eq!(graph.node_count(), 3);
+    }
+}
@@ -1,18 +1,86 @@
+use crate::viewport::Viewport;
 use gpui::*;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Signature of a `BackgroundVariant::Custom` painter: the background's bounds, the
+/// layer being painted and the current viewport.
+pub type BackgroundPaintFn =
+    dyn Fn(Bounds<Pixels>, &BackgroundProps, Viewport, &mut Window) + Send + Sync;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BackgroundVariant {
     Lines,
     Dots,
     Cross,
+    /// Painted by a user callback every frame. Custom layers are not serialized.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(BackgroundPainter),
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl BackgroundVariant {
+    pub fn custom<F>(paint: F) -> Self
+    where
+        F: Fn(Bounds<Pixels>, &BackgroundProps, Viewport, &mut Window) + Send + Sync + 'static,
+    {
+        BackgroundVariant::Custom(BackgroundPainter(Arc::new(paint)))
+    }
+}
+
+/// Paint callback of a custom background layer. Painters compare equal only to
+/// clones of themselves.
+#[derive(Clone)]
+pub struct BackgroundPainter(Arc<BackgroundPaintFn>);
+
+impl BackgroundPainter {
+    fn paint(
+        &self,
+        bounds: Bounds<Pixels>,
+        props: &BackgroundProps,
+        viewport: Viewport,
+        window: &mut Window,
+    ) {
+        (self.0)(bounds, props, viewport, window)
+    }
+}
+
+impl PartialEq for BackgroundPainter {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl fmt::Debug for BackgroundPainter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BackgroundPainter")
+    }
+}
+
+/// One pattern layer of a `Background`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BackgroundProps {
     pub variant: BackgroundVariant,
     pub gap: f32,
+    #[cfg_attr(feature = "serde", serde(with = "hsla_serde"))]
     pub color: Hsla,
     pub size: f32,
+    /// Shift of the pattern in flow units, e.g. to stagger one layer against another.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub offset: Point<f32>,
+    /// Cross-fade between grid levels. Once zooming out packs the pattern closer
+    /// than a few pixels it switches to a coarser level; without this the switch is
+    /// abrupt. At zoom 1 and above the pattern is always drawn as configured.
+    #[cfg_attr(feature = "serde", serde(default = "default_merge_levels"))]
+    pub merge_levels: bool,
+}
+
+#[cfg(feature = "serde")]
+fn default_merge_levels() -> bool {
+    true
 }
 
 impl Default for BackgroundProps {
@@ -22,132 +90,536 @@ impl Default for BackgroundProps {
             gap: 20.0,
             color: rgb(0x666666).into(),
             size: 1.0,
+            offset: Point::default(),
+            merge_levels: true,
+        }
+    }
+}
+
+/// A solid fill with pattern layers painted on top of it in order.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(from = "serde_impl::BackgroundRepr"))]
+pub struct Background {
+    #[cfg_attr(feature = "serde", serde(with = "hsla_serde"))]
+    pub color: Hsla,
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "serde_impl::serialize_layers")
+    )]
+    pub layers: Vec<BackgroundProps>,
+}
+
+impl Background {
+    /// A plain fill with no layers.
+    pub fn new(color: impl Into<Hsla>) -> Self {
+        Self {
+            color: color.into(),
+            layers: Vec::new(),
+        }
+    }
+
+    pub fn with_layer(mut self, layer: BackgroundProps) -> Self {
+        self.layers.push(layer);
+        self
+    }
+
+    /// Minor lines every `minor_gap` with major lines every `major_gap` on top.
+    pub fn grid(color: impl Into<Hsla>, minor_gap: f32, major_gap: f32) -> Self {
+        let minor = BackgroundProps {
+            variant: BackgroundVariant::Lines,
+            gap: minor_gap,
+            color: rgb(0x2c2c2c).into(),
+            ..BackgroundProps::default()
+        };
+        let major = BackgroundProps {
+            gap: major_gap,
+            color: rgb(0x3a3a3a).into(),
+            ..minor.clone()
+        };
+        Self::new(color).with_layer(minor).with_layer(major)
+    }
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self::new(rgb(0x202020)).with_layer(BackgroundProps::default())
+    }
+}
+
+/// A single layer over the default fill.
+impl From<BackgroundProps> for Background {
+    fn from(layer: BackgroundProps) -> Self {
+        Self {
+            layers: vec![layer],
+            ..Self::default()
+        }
+    }
+}
+
+/// Serializes colours as `[h, s, l, a]`.
+#[cfg(feature = "serde")]
+pub(crate) mod hsla_serde {
+    use gpui::Hsla;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(color: &Hsla, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.h, color.s, color.l, color.a].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Hsla, D::Error> {
+        let [h, s, l, a] = <[f32; 4]>::deserialize(deserializer)?;
+        Ok(Hsla { h, s, l, a })
+    }
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::{Background, BackgroundProps, BackgroundVariant, hsla_serde};
+    use gpui::Hsla;
+    use serde::{Deserialize, Serializer};
+
+    /// Custom layers hold code, so they are left out of saved backgrounds.
+    pub fn serialize_layers<S: Serializer>(
+        layers: &[BackgroundProps],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(
+            layers
+                .iter()
+                .filter(|layer| !matches!(layer.variant, BackgroundVariant::Custom(_))),
+        )
+    }
+
+    // Documents written before backgrounds had layers store a single
+    // `BackgroundProps`, which loads as one layer over the default fill.
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    pub enum BackgroundRepr {
+        Layered {
+            #[serde(with = "hsla_serde")]
+            color: Hsla,
+            layers: Vec<BackgroundProps>,
+        },
+        Single(BackgroundProps),
+    }
+
+    impl From<BackgroundRepr> for Background {
+        fn from(repr: BackgroundRepr) -> Self {
+            match repr {
+                BackgroundRepr::Layered { color, layers } => Background { color, layers },
+                BackgroundRepr::Single(layer) => layer.into(),
+            }
         }
     }
 }
 
-pub fn render_background(props: BackgroundProps) -> impl IntoElement {
+/// Screen-space gap below which a grid level hands over to a coarser one.
+const MIN_LEVEL_GAP: f32 = 4.0;
+/// Screen-space gap below which a grid level starts to fade out, unless that is
+/// above the layer's own gap: a layer is never faded at zoom 1.
+const FADE_START_GAP: f32 = 8.0;
+/// Ratio between the gaps of consecutive grid levels.
+const LEVEL_FACTOR: f32 = 4.0;
+/// Most quads a single grid level may produce; denser levels step to a coarser gap.
+const MAX_LEVEL_QUADS: f32 = 40_000.0;
+
+/// One rectangle of a background pattern, relative to the pattern origin.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternQuad {
+    pub bounds: Bounds<f32>,
+    pub corner_radius: f32,
+    pub color: Hsla,
+}
+
+/// Quads of one background layer at a given zoom level.
+///
+/// The pattern repeats every `period` pixels on both axes, so panning only moves
+/// its origin (see `origin`). The quads cover the background plus one period.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatternQuads {
+    pub quads: Vec<PatternQuad>,
+    /// Screen distance after which the pattern repeats; zero when there are no quads.
+    pub period: f32,
+}
+
+impl PatternQuads {
+    /// Position of the pattern origin relative to the background's top-left corner,
+    /// within one period above and to the left of it.
+    pub fn origin(&self, props: &BackgroundProps, viewport: Viewport) -> Point<f32> {
+        if self.period <= 0.0 {
+            return Point::default();
+        }
+        // Pattern is anchored at the flow origin so it moves with the nodes.
+        let offset = viewport.offset() + props.offset * viewport.zoom;
+        point(
+            offset.x.rem_euclid(self.period) - self.period,
+            offset.y.rem_euclid(self.period) - self.period,
+        )
+    }
+}
+
+/// Background quads from the last frame, reused while the view size, layers and
+/// zoom level are unchanged (e.g. while panning or dragging nodes).
+#[derive(Default)]
+pub struct BackgroundCache {
+    key: Option<(Size<Pixels>, Vec<BackgroundProps>, f32)>,
+    /// Quads of each layer, empty for custom layers.
+    layers: Vec<PatternQuads>,
+}
+
+impl BackgroundCache {
+    fn layers(
+        &mut self,
+        size: Size<Pixels>,
+        layers: &[BackgroundProps],
+        zoom: f32,
+    ) -> &[PatternQuads] {
+        let stale = self
+            .key
+            .as_ref()
+            .is_none_or(|(key_size, key_layers, key_zoom)| {
+                *key_size != size || key_layers.as_slice() != layers || *key_zoom != zoom
+            });
+        if stale {
+            self.layers = layers
+                .iter()
+                .map(|layer| pattern_quads(size.map(f32::from), layer, zoom))
+                .collect();
+            self.key = Some((size, layers.to_vec(), zoom));
+        }
+        &self.layers
+    }
+}
+
+pub fn render_background(
+    background: Background,
+    viewport: Viewport,
+    cache: Rc<RefCell<BackgroundCache>>,
+) -> impl IntoElement {
     div()
         .absolute()
         .left(px(0.0))
         .top(px(0.0))
         .size_full()
+        .bg(background.color)
         .child(
             canvas(
                 |_, _, _| {},
-                move |bounds, _, window, _cx| match props.variant {
-                    BackgroundVariant::Lines => {
-                        render_lines(&bounds, &props, window);
-                    }
-                    BackgroundVariant::Dots => {
-                        render_dots(&bounds, &props, window);
-                    }
-                    BackgroundVariant::Cross => {
-                        render_cross(&bounds, &props, window);
-                    }
+                move |bounds, _, window, _cx| {
+                    let mut cache = cache.borrow_mut();
+                    let patterns = cache.layers(bounds.size, &background.layers, viewport.zoom);
+                    // Patterns start up to one period before the top-left corner.
+                    window.with_content_mask(Some(ContentMask { bounds }), |window| {
+                        for (layer, pattern) in background.layers.iter().zip(patterns) {
+                            if let BackgroundVariant::Custom(painter) = &layer.variant {
+                                painter.paint(bounds, layer, viewport, window);
+                                continue;
+                            }
+                            let origin = bounds.origin + pattern.origin(layer, viewport).map(px);
+                            for quad in &pattern.quads {
+                                let quad_bounds = Bounds::new(
+                                    origin + quad.bounds.origin.map(px),
+                                    quad.bounds.size.map(px),
+                                );
+                                window.paint_quad(
+                                    fill(quad_bounds, quad.color)
+                                        .corner_radii(Corners::all(px(quad.corner_radius))),
+                                );
+                            }
+                        }
+                    });
                 },
             )
             .size_full(),
         )
 }
 
-fn render_lines(bounds: &Bounds<Pixels>, props: &BackgroundProps, window: &mut Window) {
-    let gap = props.gap;
-    let width = f32::from(bounds.size.width);
-    let height = f32::from(bounds.size.height);
-
-    let mut builder = PathBuilder::stroke(px(props.size));
+/// Quads covering a background of `size` with one layer at `zoom`, positioned
+/// relative to the pattern origin.
+///
+/// Every dot, line and cross arm is a single quad, so the GPU draws the pattern as
+/// instanced rectangles with no path tessellation. Custom layers produce no quads.
+pub fn pattern_quads(size: Size<f32>, props: &BackgroundProps, zoom: f32) -> PatternQuads {
+    let mut pattern = PatternQuads::default();
+    let gap = props.gap * zoom;
+    if gap <= 0.0 || matches!(props.variant, BackgroundVariant::Custom(_)) {
+        return pattern;
+    }
+    let mark_size = (props.size * zoom).max(0.5);
+    let cover = |period: f32| Size::new(size.width + period, size.height + period);
 
-    // Vertical lines
-    let mut x = 0.0;
-    while x <= width {
-        let screen_x = bounds.origin.x + px(x);
-        builder.move_to(point(screen_x, bounds.origin.y));
-        builder.line_to(point(screen_x, bounds.origin.y + bounds.size.height));
-        x += gap;
+    // Step to coarser levels until the spacing is readable and a level fits the
+    // quad budget, even on very large views.
+    let mut level_gap = gap;
+    while level_gap < MIN_LEVEL_GAP
+        || level_quad_count(cover(level_gap * LEVEL_FACTOR), level_gap, &props.variant)
+            > MAX_LEVEL_QUADS
+    {
+        level_gap *= LEVEL_FACTOR;
     }
 
-    // Horizontal lines
-    let mut y = 0.0;
-    while y <= height {
-        let screen_y = bounds.origin.y + px(y);
-        builder.move_to(point(bounds.origin.x, screen_y));
-        builder.line_to(point(bounds.origin.x + bounds.size.width, screen_y));
-        y += gap;
+    if !props.merge_levels {
+        pattern.period = level_gap;
+        push_pattern(
+            &mut pattern.quads,
+            cover(level_gap),
+            props,
+            level_gap,
+            mark_size,
+            props.color,
+        );
+        return pattern;
     }
 
-    if let Ok(path) = builder.build() {
-        window.paint_path(path, props.color);
+    // Fade the finest level out as it approaches the limit so zooming never makes
+    // the grid pop.
+    let fade_start = FADE_START_GAP.min(props.gap);
+    let fade = if fade_start > MIN_LEVEL_GAP {
+        ((level_gap - MIN_LEVEL_GAP) / (fade_start - MIN_LEVEL_GAP)).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    // Every level's gap divides the coarsest one drawn.
+    pattern.period = if fade < 1.0 {
+        level_gap * LEVEL_FACTOR
+    } else {
+        level_gap
+    };
+    let cover = cover(pattern.period);
+
+    if fade < 1.0 {
+        push_pattern(
+            &mut pattern.quads,
+            cover,
+            props,
+            level_gap * LEVEL_FACTOR,
+            mark_size,
+            props.color,
+        );
     }
+    if fade > 0.0 {
+        let color = props.color.opacity(fade);
+        push_pattern(
+            &mut pattern.quads,
+            cover,
+            props,
+            level_gap,
+            mark_size,
+            color,
+        );
+    }
+    pattern
 }
 
-fn render_dots(bounds: &Bounds<Pixels>, props: &BackgroundProps, window: &mut Window) {
-    let gap = props.gap;
-    let width = f32::from(bounds.size.width);
-    let height = f32::from(bounds.size.height);
-    let size = props.size.max(1.0);
-
-    let mut builder = PathBuilder::fill();
+/// Number of quads `push_pattern` produces for one level.
+fn level_quad_count(size: Size<f32>, gap: f32, variant: &BackgroundVariant) -> f32 {
+    let columns = (size.width / gap).floor() + 1.0;
+    let rows = (size.height / gap).floor() + 1.0;
+    match variant {
+        BackgroundVariant::Lines => columns + rows,
+        BackgroundVariant::Dots => columns * rows,
+        BackgroundVariant::Cross => 2.0 * columns * rows,
+        BackgroundVariant::Custom(_) => 0.0,
+    }
+}
 
-    let mut y = 0.0;
-    while y <= height {
-        let mut x = 0.0;
-        while x <= width {
-            let center = bounds.origin + point(px(x), px(y));
+/// Pushes one grid level with marks at multiples of `gap`, covering `size`.
+fn push_pattern(
+    quads: &mut Vec<PatternQuad>,
+    size: Size<f32>,
+    props: &BackgroundProps,
+    gap: f32,
+    mark_size: f32,
+    color: Hsla,
+) {
+    let columns = grid_positions(0.0, gap, size.width);
+    let rows = grid_positions(0.0, gap, size.height);
+    let mut push = |origin: Point<f32>, quad_size: Size<f32>, corner_radius: f32| {
+        quads.push(PatternQuad {
+            bounds: Bounds::new(origin, quad_size),
+            corner_radius,
+            color,
+        });
+    };
 
-            // Draw each dot as a small square for better performance
-            // Draw each dot as a circle using polygon approximation
-            let steps = 12;
-            for i in 0..=steps {
-                let theta = (i as f32 / steps as f32) * 2.0 * std::f32::consts::PI;
-                let px_x = center.x + px(size * theta.cos());
-                let px_y = center.y + px(size * theta.sin());
-                let p = point(px_x, px_y);
-                if i == 0 {
-                    builder.move_to(p);
-                } else {
-                    builder.line_to(p);
+    match props.variant {
+        BackgroundVariant::Lines => {
+            let width = props.size;
+            for x in columns {
+                push(
+                    point(x - width / 2.0, 0.0),
+                    Size::new(width, size.height),
+                    0.0,
+                );
+            }
+            for y in rows {
+                push(
+                    point(0.0, y - width / 2.0),
+                    Size::new(size.width, width),
+                    0.0,
+                );
+            }
+        }
+        BackgroundVariant::Dots => {
+            let diameter = mark_size * 2.0;
+            for y in rows {
+                for x in columns.clone() {
+                    let origin = point(x - mark_size, y - mark_size);
+                    push(origin, Size::new(diameter, diameter), mark_size);
+                }
+            }
+        }
+        BackgroundVariant::Cross => {
+            let arm = mark_size * 3.0;
+            for y in rows {
+                for x in columns.clone() {
+                    push(
+                        point(x - arm, y - mark_size / 2.0),
+                        Size::new(arm * 2.0, mark_size),
+                        0.0,
+                    );
+                    push(
+                        point(x - mark_size / 2.0, y - arm),
+                        Size::new(mark_size, arm * 2.0),
+                        0.0,
+                    );
                 }
             }
-
-            x += gap;
         }
-        y += gap;
+        BackgroundVariant::Custom(_) => {}
     }
+}
 
-    if let Ok(path) = builder.build() {
-        window.paint_path(path, props.color);
-    }
+/// Positions `start, start + gap, ...` up to and including `end`.
+fn grid_positions(start: f32, gap: f32, end: f32) -> impl Iterator<Item = f32> + Clone {
+    let count = if start > end {
+        0
+    } else {
+        ((end - start) / gap) as usize + 1
+    };
+    (0..count).map(move |i| start + i as f32 * gap)
 }
 
-fn render_cross(bounds: &Bounds<Pixels>, props: &BackgroundProps, window: &mut Window) {
-    let gap = props.gap;
-    let width = f32::from(bounds.size.width);
-    let height = f32::from(bounds.size.height);
-    let size = props.size * 3.0;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut builder = PathBuilder::stroke(px(props.size));
+    fn dots(gap: f32) -> BackgroundProps {
+        BackgroundProps {
+            gap,
+            ..BackgroundProps::default()
+        }
+    }
 
-    let mut y = 0.0;
-    while y <= height {
-        let mut x = 0.0;
-        while x <= width {
-            let center = bounds.origin + point(px(x), px(y));
+    #[test]
+    fn no_fading_at_or_above_zoom_1() {
+        for gap in [5.0, 10.0, 20.0] {
+            for zoom in [1.0, 1.5, 4.0] {
+                let props = dots(gap);
+                let pattern = pattern_quads(size(400.0, 300.0), &props, zoom);
+                assert_eq!(pattern.period, gap * zoom);
+                assert!(pattern.quads.iter().all(|quad| quad.color == props.color));
+            }
+        }
+    }
+
+    #[test]
+    fn fades_only_when_zoomed_out_to_a_small_gap() {
+        let props = dots(20.0);
+        // 10px on screen: still drawn as configured.
+        let pattern = pattern_quads(size(400.0, 300.0), &props, 0.5);
+        assert_eq!(pattern.period, 10.0);
+        assert!(pattern.quads.iter().all(|quad| quad.color == props.color));
 
-            // Draw horizontal line of the cross
-            builder.move_to(point(center.x - px(size), center.y));
-            builder.line_to(point(center.x + px(size), center.y));
+        // 5px on screen: fading out over a full-colour level 4x coarser.
+        let pattern = pattern_quads(size(400.0, 300.0), &props, 0.25);
+        assert_eq!(pattern.period, 5.0 * LEVEL_FACTOR);
+        assert!(pattern.quads.iter().any(|quad| quad.color == props.color));
+        assert!(
+            pattern
+                .quads
+                .iter()
+                .any(|quad| quad.color.a < props.color.a)
+        );
 
-            // Draw vertical line of the cross
-            builder.move_to(point(center.x, center.y - px(size)));
-            builder.line_to(point(center.x, center.y + px(size)));
+        let abrupt = BackgroundProps {
+            merge_levels: false,
+            ..props
+        };
+        let pattern = pattern_quads(size(400.0, 300.0), &abrupt, 0.25);
+        assert!(pattern.quads.iter().all(|quad| quad.color == abrupt.color));
+    }
+    fn centers(pattern: &PatternQuads) -> impl Iterator<Item = (Point<f32>, Hsla)> + '_ {
+        pattern
+            .quads
+            .iter()
+            .map(|quad| (quad.bounds.center(), quad.color))
+    }
 
-            x += gap;
+    #[test]
+    fn pattern_covers_the_view_after_panning() {
+        let view = size(410.0, 290.0);
+        for merge_levels in [false, true] {
+            for zoom in [0.1, 0.3, 1.0, 2.5] {
+                let props = BackgroundProps {
+                    offset: point(3.0, -7.0),
+                    merge_levels,
+                    ..dots(20.0)
+                };
+                let pattern = pattern_quads(view, &props, zoom);
+                for (x, y) in [(0.0, 0.0), (13.5, -41.0), (-1234.0, 987.0)] {
+                    let origin = pattern.origin(&props, Viewport::new(x, y, zoom));
+                    assert!(origin.x > -pattern.period - 1e-3 && origin.x <= 0.0);
+                    assert!(origin.y > -pattern.period - 1e-3 && origin.y <= 0.0);
+
+                    let (min, max) = centers(&pattern).fold(
+                        (point(f32::MAX, f32::MAX), point(f32::MIN, f32::MIN)),
+                        |(min, max), (center, _)| (min.min(&center), max.max(&center)),
+                    );
+                    // The first mark is at or before the view's top-left corner and the
+                    // next one after the last would fall past its bottom-right corner.
+                    assert!(origin.x + min.x <= 0.0 && origin.y + min.y <= 0.0);
+                    assert!(origin.x + max.x + pattern.period > view.width);
+                    assert!(origin.y + max.y + pattern.period > view.height);
+                }
+            }
         }
-        y += gap;
     }
 
-    if let Ok(path) = builder.build() {
-        window.paint_path(path, props.color);
+    #[test]
+    fn period_is_a_multiple_of_every_drawn_gap() {
+        for gap in [5.0, 20.0, 30.0] {
+            for merge_levels in [false, true] {
+                for zoom in [0.05, 0.1, 0.2, 0.25, 0.3, 0.37, 0.5, 1.0, 2.0] {
+                    let props = BackgroundProps {
+                        merge_levels,
+                        ..dots(gap)
+                    };
+                    let pattern = pattern_quads(size(400.0, 300.0), &props, zoom);
+                    assert!(pattern.period > 0.0);
+
+                    // Levels are told apart by their colour; each is a regular grid.
+                    let mut levels: Vec<(Hsla, Vec<f32>)> = Vec::new();
+                    for (center, color) in centers(&pattern) {
+                        match levels.iter_mut().find(|(level, _)| *level == color) {
+                            Some((_, xs)) => xs.push(center.x),
+                            None => levels.push((color, vec![center.x])),
+                        }
+                    }
+                    for (_, mut xs) in levels {
+                        xs.sort_by(f32::total_cmp);
+                        xs.dedup();
+                        let level_gap = xs[1] - xs[0];
+                        let ratio = pattern.period / level_gap;
+                        assert!(
+                            (ratio - ratio.round()).abs() < 1e-3,
+                            "gap {gap} zoom {zoom}: period {} level gap {level_gap}",
+                            pattern.period
+                        );
+                    }
+                }
+            }
+        }
     }
 }
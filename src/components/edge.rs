@@ -1,72 +1,125 @@
-use crate::graph::Edge;
+use crate::geometry::edge_curve;
 use gpui::PathBuilder;
 use gpui::*;
 
-pub fn render_edge(
-    _edge: &Edge,
-    source_pos: Point<f32>,
-    target_pos: Point<f32>,
-    _cx: &Window,
-) -> impl IntoElement {
-    // Draw edge directly using absolute coordinates.
-    let start_px = source_pos.map(px);
-    let end_px = target_pos.map(px);
-    let control_1 = point(
-        source_pos.x,
-        source_pos.y + (target_pos.y - source_pos.y) / 2.0,
-    );
-    let control_2 = point(
-        target_pos.x,
-        target_pos.y - (target_pos.y - source_pos.y) / 2.0,
-    );
-    let control_1_px = control_1.map(px);
-    let control_2_px = control_2.map(px);
+/// Stroke of an edge. Edges with equal styles are painted as one shared path.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeStyle {
+    pub color: Hsla,
+    /// Stroke width in screen pixels.
+    pub width: f32,
+}
+
+impl EdgeStyle {
+    pub fn new(color: impl Into<Hsla>) -> Self {
+        Self {
+            color: color.into(),
+            width: 2.0,
+        }
+    }
+
+    pub fn with_width(mut self, width: f32) -> Self {
+        self.width = width;
+        self
+    }
+
+    /// Brighter and thicker, for the edge under the pointer.
+    pub fn hovered(mut self) -> Self {
+        self.color.l = (self.color.l + 0.15).min(1.0);
+        self.width += 1.0;
+        self
+    }
+
+    /// Drawn in the selection colour, for selected edges.
+    pub fn selected(self) -> Self {
+        Self {
+            color: selected_edge_color(),
+            width: self.width + 1.0,
+        }
+    }
+}
 
-    div()
-        .absolute()
-        .left(px(0.0))
-        .top(px(0.0))
-        .size_full()
-        .child(
-            canvas(
-                |_, _, _| {},
-                move |bounds, _, window, _cx| {
-                    let start_px = bounds.origin + start_px;
-                    let end_px = bounds.origin + end_px;
-                    let control_1_px = bounds.origin + control_1_px;
-                    let control_2_px = bounds.origin + control_2_px;
+impl Default for EdgeStyle {
+    fn default() -> Self {
+        Self::new(default_edge_color())
+    }
+}
 
-                    // Manually subdivide bezier curve to ensure correct rendering
-                    let steps = 20;
-                    let mut builder = PathBuilder::stroke(px(2.0));
-                    builder.move_to(start_px);
+/// One edge to paint, with endpoints relative to the edge layer's origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EdgeStroke {
+    pub source: Point<f32>,
+    pub target: Point<f32>,
+    pub style: EdgeStyle,
+}
 
-                    for i in 1..=steps {
-                        let t = i as f32 / steps as f32;
-                        let t_inv = 1.0 - t;
+/// Appends the curve of a single edge to `builder` as a new sub-path.
+pub fn render_edge(
+    source_pos: Point<Pixels>,
+    target_pos: Point<Pixels>,
+    builder: &mut PathBuilder,
+) {
+    let mut points =
+        edge_curve(source_pos.map(f32::from), target_pos.map(f32::from)).map(|p| p.map(px));
+    // Curves are flattened by hand so the stroke tessellates the same at any zoom.
+    if let Some(start) = points.next() {
+        builder.move_to(start);
+    }
+    for point in points {
+        builder.line_to(point);
+    }
+}
 
-                        // Cubic bezier formula: (1-t)^3 P0 + 3(1-t)^2 t P1 + 3(1-t) t^2 P2 + t^3 P3
-                        let p0 = start_px;
-                        let p1 = control_1_px;
-                        let p2 = control_2_px;
-                        let p3 = end_px;
+/// Colour used for edges whose source handle has no port type.
+pub fn default_edge_color() -> Hsla {
+    rgb(0xaaaaaa).into()
+}
 
-                        let x = t_inv.powi(3) * f32::from(p0.x)
-                            + 3.0 * t_inv.powi(2) * t * f32::from(p1.x)
-                            + 3.0 * t_inv * t.powi(2) * f32::from(p2.x)
-                            + t.powi(3) * f32::from(p3.x);
+/// Colour of selected edges.
+pub fn selected_edge_color() -> Hsla {
+    rgb(0x4a90e2).into()
+}
 
-                        let y = t_inv.powi(3) * f32::from(p0.y)
-                            + 3.0 * t_inv.powi(2) * t * f32::from(p1.y)
-                            + 3.0 * t_inv * t.powi(2) * f32::from(p2.y)
-                            + t.powi(3) * f32::from(p3.y);
+/// Line drawn from the handle being dragged to the cursor while a connection is in progress.
+pub fn render_connection_line(source_pos: Point<f32>, target_pos: Point<f32>) -> impl IntoElement {
+    render_edges(vec![EdgeStroke {
+        source: source_pos,
+        target: target_pos,
+        style: EdgeStyle::new(rgb(0xffffff)),
+    }])
+}
 
-                        builder.line_to(point(px(x), px(y)));
+/// Paints every stroke from a single canvas, building one path per distinct style
+/// so large graphs cost a handful of draw calls rather than one element per edge.
+pub fn render_edges(strokes: Vec<EdgeStroke>) -> impl IntoElement {
+    canvas(
+        |_, _, _| {},
+        move |bounds, _, window, _cx| {
+            let mut paths: Vec<(EdgeStyle, PathBuilder)> = Vec::new();
+            for stroke in &strokes {
+                let index = match paths.iter().position(|(style, _)| *style == stroke.style) {
+                    Some(index) => index,
+                    None => {
+                        paths.push((stroke.style, PathBuilder::stroke(px(stroke.style.width))));
+                        paths.len() - 1
                     }
+                };
+                render_edge(
+                    bounds.origin + stroke.source.map(px),
+                    bounds.origin + stroke.target.map(px),
+                    &mut paths[index].1,
+                );
+            }
 
-                    window.paint_path(builder.build().unwrap(), rgb(0xaaaaaa));
-                },
-            )
-            .size_full(),
-        )
+            for (style, builder) in paths {
+                if let Ok(path) = builder.build() {
+                    window.paint_path(path, style.color);
+                }
+            }
+        },
+    )
+    .absolute()
+    .left(px(0.0))
+    .top(px(0.0))
+    .size_full()
 }
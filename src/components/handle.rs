@@ -1,15 +1,22 @@
-use crate::types::{handle::HandleType, position::Position};
+use crate::types::{
+    handle::HandleType,
+    port::{Multiplicity, PortType},
+    position::Position,
+};
+use gpui::*;
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Handle {
     pub id: String,
     pub handle_type: HandleType,
     pub position: Position,
-    pub offset: Option<f32>, // Percentage or pixel offset from center? Let's say 0.0 to 1.0 along the edge? Or just rely on flex/absolute positioning?
-                             // For now, let's stick to simple positioning logic in render.
-                             // Actually, the user might want multiple handles on one side.
-                             // Let's add an index or just rely on the order in the vector?
-                             // Let's keep it simple: The Node struct holds the handles.
+    /// Fraction (0.0–1.0) along the node side; spread out automatically when `None`.
+    pub offset: Option<f32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub port_type: Option<PortType>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub multiplicity: Multiplicity,
 }
 
 impl Handle {
@@ -19,6 +26,42 @@ impl Handle {
             handle_type,
             position,
             offset: None,
+            port_type: None,
+            multiplicity: Multiplicity::default(),
         }
     }
+
+    pub fn with_offset(mut self, offset: f32) -> Self {
+        self.offset = Some(offset.clamp(0.0, 1.0));
+        self
+    }
+
+    /// Restricts connections to handles with a compatible `PortType`.
+    pub fn with_port_type(mut self, port_type: PortType) -> Self {
+        self.port_type = Some(port_type);
+        self
+    }
+
+    pub fn with_multiplicity(mut self, multiplicity: Multiplicity) -> Self {
+        self.multiplicity = multiplicity;
+        self
+    }
+}
+
+/// Draws a handle dot. `bounds` are relative to the node's top-left corner, already scaled
+/// to screen pixels.
+pub fn render_handle(handle: &Handle, bounds: Bounds<Pixels>) -> impl IntoElement {
+    div()
+        .absolute()
+        .left(bounds.origin.x)
+        .top(bounds.origin.y)
+        .w(bounds.size.width)
+        .h(bounds.size.height)
+        .bg(handle
+            .port_type
+            .as_ref()
+            .map_or(rgb(0xffffff).into(), |port| port.color))
+        .rounded_full()
+        .border_1()
+        .border_color(rgb(0x000000))
 }
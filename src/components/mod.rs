@@ -2,3 +2,4 @@ pub mod background;
 pub mod edge;
 pub mod handle;
 pub mod node;
+pub mod zoom;
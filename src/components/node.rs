@@ -1,51 +1,42 @@
-use crate::graph::Node;
-use crate::types::position::Position;
+use crate::graph::{DEFAULT_NODE_SIZE, Node};
 use gpui::*;
 
+/// Per-node state passed to node renderers alongside the node itself.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NodeRenderContext {
+    /// Current zoom level. Node content is rendered with the rem size scaled by this
+    /// factor, so rem-based styles scale automatically; pixel lengths should go
+    /// through `NodeRenderContext::px`.
+    pub zoom: f32,
+    /// Whether the node is selected; renderers usually draw a highlight for it.
+    pub selected: bool,
+}
+
+impl NodeRenderContext {
+    /// Converts a length in flow units to on-screen pixels at the current zoom.
+    pub fn px(&self, value: f32) -> Pixels {
+        px(value * self.zoom)
+    }
+}
+
 pub fn render_node<D: Clone + Send + Sync + 'static>(
-    node: &Node<D>,
+    _node: &Node<D>,
+    ctx: &NodeRenderContext,
     _cx: &Window,
 ) -> impl IntoElement {
     div()
         .size_full()
+        .min_w(ctx.px(DEFAULT_NODE_SIZE.width))
+        .min_h(ctx.px(DEFAULT_NODE_SIZE.height))
         .bg(rgb(0x303030))
-        .border_1()
-        .border_color(rgb(0x000000))
+        .border(ctx.px(if ctx.selected { 2.0 } else { 1.0 }))
+        .border_color(if ctx.selected {
+            rgb(0x4a90e2)
+        } else {
+            rgb(0x000000)
+        })
         .rounded_md()
         .shadow_md()
-        .relative() // Make sure handles can be positioned absolutely relative to this
-        .children(node.handles.iter().map(|handle| {
-            let (t, b, l, r) = match handle.position {
-                Position::Top => (Some(px(-5.0)), None, Some(px(75.0 - 5.0)), None), // Center top
-                Position::Bottom => (None, Some(px(-5.0)), Some(px(75.0 - 5.0)), None), // Center bottom
-                Position::Left => (Some(px(40.0 - 5.0)), None, Some(px(-5.0)), None), // Center left
-                Position::Right => (Some(px(40.0 - 5.0)), None, None, Some(px(-5.0))), // Center right
-            };
-
-            let mut div = div()
-                .absolute()
-                .w(px(10.0))
-                .h(px(10.0))
-                .bg(rgb(0xffffff))
-                .rounded_full()
-                .border_1()
-                .border_color(rgb(0x000000));
-
-            if let Some(val) = t {
-                div = div.top(val);
-            }
-            if let Some(val) = b {
-                div = div.bottom(val);
-            }
-            if let Some(val) = l {
-                div = div.left(val);
-            }
-            if let Some(val) = r {
-                div = div.right(val);
-            }
-
-            div
-        }))
         .child(
             div()
                 .flex()
@@ -54,7 +45,7 @@ pub fn render_node<D: Clone + Send + Sync + 'static>(
                 .child(
                     div()
                         .w_full()
-                        .h(px(24.0))
+                        .h(rems(1.5))
                         .bg(rgb(0x404040))
                         .rounded_t_md()
                         .flex()
@@ -0,0 +1,91 @@
+use gpui::*;
+
+/// Lays out and paints `child` with the rem size multiplied by `zoom`.
+///
+/// GPUI has no transform for element subtrees, so the view scales node content
+/// by scaling the rem unit instead: anything styled in rems (text sizes, the
+/// `p_*`/`gap_*`/`rounded_*` helpers, `rems(..)` lengths) follows the zoom level.
+/// Pixel lengths are unaffected; use `NodeRenderContext::px` for those.
+pub fn zoomed(zoom: f32, child: impl IntoElement) -> Zoomed {
+    Zoomed {
+        zoom,
+        child: child.into_any_element(),
+    }
+}
+
+pub struct Zoomed {
+    zoom: f32,
+    child: AnyElement,
+}
+
+impl Zoomed {
+    fn rem_size(&self, window: &Window) -> Pixels {
+        window.rem_size() * self.zoom
+    }
+}
+
+impl IntoElement for Zoomed {
+    type Element = Self;
+
+    fn into_element(self) -> Self::Element {
+        self
+    }
+}
+
+impl Element for Zoomed {
+    type RequestLayoutState = ();
+    type PrepaintState = ();
+
+    fn id(&self) -> Option<ElementId> {
+        None
+    }
+
+    fn source_location(&self) -> Option<&'static core::panic::Location<'static>> {
+        None
+    }
+
+    fn request_layout(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> (LayoutId, Self::RequestLayoutState) {
+        let rem_size = self.rem_size(window);
+        let layout_id = window.with_rem_size(Some(rem_size), |window| {
+            self.child.request_layout(window, cx)
+        });
+        (layout_id, ())
+    }
+
+    fn prepaint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        _bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Self::PrepaintState {
+        let rem_size = self.rem_size(window);
+        window.with_rem_size(Some(rem_size), |window| {
+            self.child.prepaint(window, cx);
+        });
+    }
+
+    fn paint(
+        &mut self,
+        _id: Option<&GlobalElementId>,
+        _inspector_id: Option<&InspectorElementId>,
+        _bounds: Bounds<Pixels>,
+        _request_layout: &mut Self::RequestLayoutState,
+        _prepaint: &mut Self::PrepaintState,
+        window: &mut Window,
+        cx: &mut App,
+    ) {
+        let rem_size = self.rem_size(window);
+        window.with_rem_size(Some(rem_size), |window| {
+            self.child.paint(window, cx);
+        });
+    }
+}
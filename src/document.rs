@@ -0,0 +1,181 @@
+use crate::components::background::Background;
+use crate::graph::Graph;
+use crate::viewport::Viewport;
+use gpui::Point;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// Version written by `FlowDocument::to_json`. Bump when the format changes
+/// incompatibly and teach `from_json` to migrate older versions.
+///
+/// Version 1 stored the viewport as `{ pan_offset, zoom_level }`.
+pub const DOCUMENT_VERSION: u32 = 2;
+
+/// Versioned on-disk representation of a flow: graph, viewport and background.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(bound(serialize = "D: Serialize", deserialize = "D: Deserialize<'de>"))]
+pub struct FlowDocument<D> {
+    pub version: u32,
+    pub graph: Graph<D>,
+    #[serde(default, deserialize_with = "deserialize_viewport")]
+    pub viewport: Viewport,
+    #[serde(default)]
+    pub background: Background,
+}
+
+impl<D> FlowDocument<D> {
+    pub fn new(graph: Graph<D>, viewport: Viewport, background: Background) -> Self {
+        Self {
+            version: DOCUMENT_VERSION,
+            graph,
+            viewport,
+            background,
+        }
+    }
+}
+
+impl<D: Serialize> FlowDocument<D> {
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Writes a current-version document without building a `FlowDocument`, so
+    /// callers that own the parts need not clone them.
+    pub fn parts_to_json(
+        graph: &Graph<D>,
+        viewport: Viewport,
+        background: &Background,
+    ) -> Result<String, DocumentError> {
+        let document = FlowDocumentRef {
+            version: DOCUMENT_VERSION,
+            graph,
+            viewport,
+            background,
+        };
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+}
+
+/// Borrowed counterpart of `FlowDocument`, serialized with the same layout.
+#[derive(Serialize)]
+#[serde(bound(serialize = "D: Serialize"))]
+struct FlowDocumentRef<'a, D> {
+    version: u32,
+    graph: &'a Graph<D>,
+    viewport: Viewport,
+    background: &'a Background,
+}
+
+impl<D: DeserializeOwned> FlowDocument<D> {
+    /// Parses a document, migrating older versions to `DOCUMENT_VERSION`.
+    pub fn from_json(json: &str) -> Result<Self, DocumentError> {
+        let mut document: Self = serde_json::from_str(json)?;
+        if document.version > DOCUMENT_VERSION {
+            return Err(DocumentError::UnsupportedVersion(document.version));
+        }
+        document.version = DOCUMENT_VERSION;
+        Ok(document)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ViewportRepr {
+    Current(Viewport),
+    /// Version 1 layout.
+    Legacy {
+        pan_offset: Point<f32>,
+        zoom_level: f32,
+    },
+}
+
+fn deserialize_viewport<'de, De: Deserializer<'de>>(
+    deserializer: De,
+) -> Result<Viewport, De::Error> {
+    Ok(match ViewportRepr::deserialize(deserializer)? {
+        ViewportRepr::Current(viewport) => viewport,
+        ViewportRepr::Legacy {
+            pan_offset,
+            zoom_level,
+        } => Viewport::new(pan_offset.x, pan_offset.y, zoom_level),
+    })
+}
+
+#[derive(Debug)]
+pub enum DocumentError {
+    Json(serde_json::Error),
+    /// The document was written by a newer version of the format.
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for DocumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DocumentError::Json(err) => write!(f, "invalid flow document: {err}"),
+            DocumentError::UnsupportedVersion(version) => write!(
+                f,
+                "unsupported flow document version {version} (expected at most {DOCUMENT_VERSION})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DocumentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DocumentError::Json(err) => Some(err),
+            DocumentError::UnsupportedVersion(_) => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for DocumentError {
+    fn from(err: serde_json::Error) -> Self {
+        DocumentError::Json(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrates_version_1_viewport() {
+        let json =
+            FlowDocument::<()>::new(Graph::new(), Viewport::default(), Background::default())
+                .to_json()
+                .unwrap();
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["version"] = 1.into();
+        value["viewport"] = serde_json::json!({
+            "pan_offset": { "x": 10.0, "y": -20.0 },
+            "zoom_level": 2.0,
+        });
+
+        let document = FlowDocument::<()>::from_json(&value.to_string()).unwrap();
+        assert_eq!(document.version, DOCUMENT_VERSION);
+        assert_eq!(document.viewport, Viewport::new(10.0, -20.0, 2.0));
+    }
+
+    #[test]
+    fn round_trips_viewport() {
+        let viewport = Viewport::new(1.0, 2.0, 0.5);
+        let json = FlowDocument::<()>::new(Graph::new(), viewport, Background::default())
+            .to_json()
+            .unwrap();
+        assert_eq!(
+            FlowDocument::<()>::from_json(&json).unwrap().viewport,
+            viewport
+        );
+    }
+
+    #[test]
+    fn parts_match_the_owned_document() {
+        let viewport = Viewport::new(1.0, 2.0, 0.5);
+        let graph = Graph::<()>::new();
+        let borrowed = FlowDocument::parts_to_json(&graph, viewport, &Background::default());
+        let owned = FlowDocument::new(graph, viewport, Background::default()).to_json();
+        assert_eq!(borrowed.unwrap(), owned.unwrap());
+    }
+}
@@ -0,0 +1,22 @@
+use crate::graph::Connection;
+use crate::viewport::Viewport;
+use gpui::Point;
+use uuid::Uuid;
+
+/// Events emitted by `GraphView`; subscribe with `cx.subscribe`.
+///
+/// Positions are in flow coordinates unless stated otherwise.
+#[derive(Clone, Debug)]
+pub enum GraphEvent {
+    NodeClick { id: Uuid },
+    NodeDoubleClick { id: Uuid },
+    NodeDragStart { id: Uuid, position: Point<f32> },
+    NodeDrag { id: Uuid, position: Point<f32> },
+    NodeDragStop { id: Uuid, position: Point<f32> },
+    EdgeClick { id: Uuid },
+    EdgeDoubleClick { id: Uuid },
+    PaneClick { position: Point<f32> },
+    Connect(Connection),
+    ViewportChanged(Viewport),
+    SelectionChanged { nodes: Vec<Uuid>, edges: Vec<Uuid> },
+}
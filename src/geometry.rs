@@ -0,0 +1,220 @@
+//! Node, handle and edge geometry in flow coordinates.
+//!
+//! Rendering, hit testing and connection logic all go through these functions so
+//! that what is drawn and what is clickable always agree. Multiply by the zoom
+//! level (and add the pan offset) to get screen coordinates.
+
+use crate::components::handle::Handle;
+use crate::graph::{Edge, Node};
+use crate::types::position::Position;
+use gpui::{Bounds, Point, point, size};
+
+/// Diameter of a drawn handle.
+pub const HANDLE_SIZE: f32 = 10.0;
+
+/// Smallest diameter, in screen pixels, of the circle around a handle's anchor
+/// that starts or completes a connection when pressed.
+pub const HANDLE_HIT_SIZE: f32 = 16.0;
+
+/// Radius, in flow units, within which a press at `zoom` hits a handle: at least
+/// `HANDLE_HIT_SIZE` across on screen, and never smaller than the drawn dot.
+pub fn handle_hit_radius(zoom: f32) -> f32 {
+    (HANDLE_HIT_SIZE / 2.0 / zoom).max(HANDLE_SIZE / 2.0)
+}
+
+pub fn node_bounds<D>(node: &Node<D>) -> Bounds<f32> {
+    Bounds::new(node.position, node.size())
+}
+
+/// Anchor of `handle` relative to the node's top-left corner.
+///
+/// Handles with an explicit `offset` sit at that fraction of their side; the
+/// rest are spread evenly along their side in declaration order.
+pub fn handle_offset<D>(node: &Node<D>, handle: &Handle) -> Point<f32> {
+    let fraction = handle.offset.unwrap_or_else(|| {
+        let mut auto = node
+            .handles
+            .iter()
+            .filter(|h| h.position == handle.position && h.offset.is_none());
+        let count = auto.clone().count();
+        let index = auto.position(|h| h.id == handle.id).unwrap_or(0);
+        (index + 1) as f32 / (count + 1) as f32
+    });
+
+    let size = node.size();
+    match handle.position {
+        Position::Top => point(size.width * fraction, 0.0),
+        Position::Bottom => point(size.width * fraction, size.height),
+        Position::Left => point(0.0, size.height * fraction),
+        Position::Right => point(size.width, size.height * fraction),
+    }
+}
+
+/// Point where edges attach to `handle`.
+pub fn handle_anchor<D>(node: &Node<D>, handle: &Handle) -> Point<f32> {
+    node.position + handle_offset(node, handle)
+}
+
+/// Bounds of the drawn handle dot.
+pub fn handle_bounds<D>(node: &Node<D>, handle: &Handle) -> Bounds<f32> {
+    centered_square(handle_anchor(node, handle), HANDLE_SIZE)
+}
+
+/// Start and end points of `edge`.
+///
+/// Edges without a (known) source handle leave from the bottom centre of the
+/// source node; edges without a target handle arrive at its top centre.
+pub fn edge_endpoints<D>(
+    edge: &Edge,
+    source: &Node<D>,
+    target: &Node<D>,
+) -> (Point<f32>, Point<f32>) {
+    let source_pos = find_handle(source, &edge.source_handle_id)
+        .map(|handle| handle_anchor(source, handle))
+        .unwrap_or_else(|| {
+            let size = source.size();
+            source.position + point(size.width / 2.0, size.height)
+        });
+    let target_pos = find_handle(target, &edge.target_handle_id)
+        .map(|handle| handle_anchor(target, handle))
+        .unwrap_or_else(|| {
+            let size = target.size();
+            target.position + point(size.width / 2.0, 0.0)
+        });
+    (source_pos, target_pos)
+}
+
+/// Box containing the whole curve of an edge between `source` and `target`.
+///
+/// The curve's control points lie inside the box spanned by its ends, and so
+/// does the curve.
+pub fn edge_bounds(source: Point<f32>, target: Point<f32>) -> Bounds<f32> {
+    Bounds::from_corners(
+        point(source.x.min(target.x), source.y.min(target.y)),
+        point(source.x.max(target.x), source.y.max(target.y)),
+    )
+}
+
+/// Number of straight segments an edge curve is flattened into.
+pub const EDGE_CURVE_SEGMENTS: usize = 20;
+
+/// Points along the curve of an edge from `source` to `target`, both included.
+///
+/// Edges are cubic béziers whose control points sit halfway between the ends
+/// vertically, so they leave and arrive vertically.
+pub fn edge_curve(source: Point<f32>, target: Point<f32>) -> impl Iterator<Item = Point<f32>> {
+    let half = (target.y - source.y) / 2.0;
+    let control_1 = point(source.x, source.y + half);
+    let control_2 = point(target.x, target.y - half);
+
+    (0..=EDGE_CURVE_SEGMENTS).map(move |i| {
+        let t = i as f32 / EDGE_CURVE_SEGMENTS as f32;
+        let t_inv = 1.0 - t;
+        // (1-t)^3 P0 + 3(1-t)^2 t P1 + 3(1-t) t^2 P2 + t^3 P3
+        source * t_inv.powi(3)
+            + control_1 * (3.0 * t_inv.powi(2) * t)
+            + control_2 * (3.0 * t_inv * t.powi(2))
+            + target * t.powi(3)
+    })
+}
+
+/// Shortest distance from `position` to the curve of an edge between `source`
+/// and `target`.
+pub fn distance_to_edge(position: Point<f32>, source: Point<f32>, target: Point<f32>) -> f32 {
+    let mut points = edge_curve(source, target);
+    let Some(mut previous) = points.next() else {
+        return f32::INFINITY;
+    };
+    let mut distance = f32::INFINITY;
+    for next in points {
+        distance = distance.min(distance_to_segment(position, previous, next));
+        previous = next;
+    }
+    distance
+}
+
+fn distance_to_segment(position: Point<f32>, start: Point<f32>, end: Point<f32>) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.x * segment.x + segment.y * segment.y;
+    let t = if length_squared > 0.0 {
+        let along = (position.x - start.x) * segment.x + (position.y - start.y) * segment.y;
+        (along / length_squared).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let closest = start + segment * t;
+    (position.x - closest.x).hypot(position.y - closest.y)
+}
+
+fn find_handle<'a, D>(node: &'a Node<D>, handle_id: &Option<String>) -> Option<&'a Handle> {
+    let handle_id = handle_id.as_ref()?;
+    node.handles.iter().find(|h| &h.id == handle_id)
+}
+
+fn centered_square(center: Point<f32>, side: f32) -> Bounds<f32> {
+    Bounds::new(center - point(side / 2.0, side / 2.0), size(side, side))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::handle::HandleType;
+
+    #[test]
+    fn hit_radius_covers_the_drawn_handle() {
+        for zoom in [0.1, 0.5, 1.0, 1.6, 2.0, 5.0] {
+            // Both measured on screen.
+            assert!(handle_hit_radius(zoom) * zoom >= HANDLE_SIZE / 2.0 * zoom);
+            assert!(handle_hit_radius(zoom) * zoom >= HANDLE_HIT_SIZE / 2.0 - f32::EPSILON);
+        }
+    }
+
+    fn node(handles: Vec<Handle>) -> Node<()> {
+        Node::new((), point(0.0, 0.0))
+            .with_size(150.0, 90.0)
+            .with_handles(handles)
+    }
+
+    fn assert_offset(node: &Node<()>, id: &str, expected: Point<f32>) {
+        let handle = node.handles.iter().find(|h| h.id == id).unwrap();
+        let offset = handle_offset(node, handle);
+        assert!(
+            (offset.x - expected.x).abs() < 1e-4 && (offset.y - expected.y).abs() < 1e-4,
+            "{id}: {offset:?} != {expected:?}"
+        );
+    }
+
+    #[test]
+    fn spreads_handles_without_offsets_along_their_side() {
+        let node = node(vec![
+            Handle::new("in1", HandleType::Target, Position::Left),
+            Handle::new("in2", HandleType::Target, Position::Left),
+            Handle::new("out", HandleType::Source, Position::Right),
+        ]);
+        assert_offset(&node, "in1", point(0.0, 30.0));
+        assert_offset(&node, "in2", point(0.0, 60.0));
+        assert_offset(&node, "out", point(150.0, 45.0));
+    }
+
+    #[test]
+    fn uses_explicit_offsets() {
+        let node = node(vec![
+            Handle::new("top", HandleType::Target, Position::Top).with_offset(0.2),
+            Handle::new("bottom", HandleType::Source, Position::Bottom).with_offset(1.0),
+        ]);
+        assert_offset(&node, "top", point(30.0, 0.0));
+        assert_offset(&node, "bottom", point(150.0, 90.0));
+    }
+
+    #[test]
+    fn explicit_offsets_do_not_take_auto_slots() {
+        let node = node(vec![
+            Handle::new("in1", HandleType::Target, Position::Left),
+            Handle::new("pinned", HandleType::Target, Position::Left).with_offset(0.1),
+            Handle::new("in2", HandleType::Target, Position::Left),
+        ]);
+        assert_offset(&node, "pinned", point(0.0, 9.0));
+        assert_offset(&node, "in1", point(0.0, 30.0));
+        assert_offset(&node, "in2", point(0.0, 60.0));
+    }
+}
@@ -1,15 +1,36 @@
 pub use crate::components::handle::Handle;
+use crate::geometry::{distance_to_edge, edge_bounds, edge_endpoints, handle_anchor, node_bounds};
+use crate::spatial::{SpatialIndex, overlaps};
 pub use crate::types::{handle::HandleType, position::Position};
-use gpui::Point;
+use gpui::{Bounds, Point, Size, point, size};
+use std::collections::HashMap;
+use std::mem;
 use uuid::Uuid;
 
+/// Size used for nodes that have neither an explicit nor a measured size.
+pub const DEFAULT_NODE_SIZE: Size<f32> = Size {
+    width: 150.0,
+    height: 80.0,
+};
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Node<D> {
     pub id: Uuid,
     pub position: Point<f32>,
     pub data: D,
     pub handles: Vec<Handle>,
     pub node_type: String,
+    /// Size set by the caller; takes precedence over `measured`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub width: Option<f32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub height: Option<f32>,
+    /// Size reported by layout, applied through `NodeChange::Dimensions`.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub measured: Option<Size<f32>>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub selected: bool,
 }
 
 impl<D> Node<D> {
@@ -20,9 +41,29 @@ impl<D> Node<D> {
             data,
             handles: Vec::new(),
             node_type: "default".to_string(),
+            width: None,
+            height: None,
+            measured: None,
+            selected: false,
         }
     }
 
+    pub fn with_size(mut self, width: f32, height: f32) -> Self {
+        self.width = Some(width);
+        self.height = Some(height);
+        self
+    }
+
+    /// Size used for layout and hit testing: explicit dimensions first, then the
+    /// measured size, then `DEFAULT_NODE_SIZE`.
+    pub fn size(&self) -> Size<f32> {
+        let fallback = self.measured.unwrap_or(DEFAULT_NODE_SIZE);
+        size(
+            self.width.unwrap_or(fallback.width),
+            self.height.unwrap_or(fallback.height),
+        )
+    }
+
     pub fn with_handles(mut self, handles: Vec<Handle>) -> Self {
         self.handles = handles;
         self
@@ -35,12 +76,15 @@ impl<D> Node<D> {
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Edge {
     pub id: Uuid,
     pub source_id: Uuid,
     pub source_handle_id: Option<String>,
     pub target_id: Uuid,
     pub target_handle_id: Option<String>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub selected: bool,
 }
 
 impl Edge {
@@ -51,6 +95,7 @@ impl Edge {
             source_handle_id: None,
             target_id,
             target_handle_id: None,
+            selected: false,
         }
     }
 
@@ -65,10 +110,53 @@ impl Edge {
     }
 }
 
+/// A requested link between two (optional) handles, before it becomes an `Edge`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Connection {
+    pub source_id: Uuid,
+    pub source_handle_id: Option<String>,
+    pub target_id: Uuid,
+    pub target_handle_id: Option<String>,
+}
+
+impl From<&Edge> for Connection {
+    fn from(edge: &Edge) -> Self {
+        Self {
+            source_id: edge.source_id,
+            source_handle_id: edge.source_handle_id.clone(),
+            target_id: edge.target_id,
+            target_handle_id: edge.target_handle_id.clone(),
+        }
+    }
+}
+
+impl From<Connection> for Edge {
+    fn from(connection: Connection) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            source_id: connection.source_id,
+            source_handle_id: connection.source_handle_id,
+            target_id: connection.target_id,
+            target_handle_id: connection.target_handle_id,
+            selected: false,
+        }
+    }
+}
+
+/// Nodes and edges of a flow.
+///
+/// Nodes and edges are stored in insertion order (which is also the z-order
+/// used when rendering) alongside an id -> index map so lookups by id are O(1),
+/// and a spatial index so area queries such as `nodes_in_rect` only look at
+/// nearby nodes and edges.
 #[derive(Clone, Debug, Default)]
 pub struct Graph<D> {
-    pub nodes: Vec<Node<D>>,
-    pub edges: Vec<Edge>,
+    nodes: Vec<Node<D>>,
+    edges: Vec<Edge>,
+    node_index: HashMap<Uuid, usize>,
+    edge_index: HashMap<Uuid, usize>,
+    spatial: SpatialIndex,
 }
 
 impl<D> Graph<D> {
@@ -76,18 +164,568 @@ impl<D> Graph<D> {
         Self {
             nodes: Vec::new(),
             edges: Vec::new(),
+            node_index: HashMap::new(),
+            edge_index: HashMap::new(),
+            spatial: SpatialIndex::default(),
         }
     }
 
+    /// Adds a node. A node with the same id as an existing one replaces it in place.
     pub fn add_node(&mut self, node: Node<D>) {
-        self.nodes.push(node);
+        self.refresh_index();
+        let id = node.id;
+        if let Some(&index) = self.node_index.get(&id) {
+            self.nodes[index] = node;
+        } else {
+            self.node_index.insert(id, self.nodes.len());
+            self.nodes.push(node);
+        }
+        self.index_node(id);
     }
 
+    /// Adds an edge. An edge with the same id as an existing one replaces it in place.
     pub fn add_edge(&mut self, edge: Edge) {
-        self.edges.push(edge);
+        self.refresh_index();
+        let id = edge.id;
+        if let Some(&index) = self.edge_index.get(&id) {
+            self.edges[index] = edge;
+        } else {
+            self.edge_index.insert(id, self.edges.len());
+            self.edges.push(edge);
+        }
+        self.index_edge(id);
+    }
+
+    /// Inserts a node at `index` in the z-order, clamped to the node count.
+    /// A node with the same id as an existing one replaces it in place.
+    pub fn insert_node(&mut self, index: usize, node: Node<D>) {
+        if self.node_index.contains_key(&node.id) {
+            self.add_node(node);
+            return;
+        }
+        self.refresh_index();
+        let id = node.id;
+        let index = index.min(self.nodes.len());
+        self.nodes.insert(index, node);
+        for (i, n) in self.nodes.iter().enumerate().skip(index) {
+            self.node_index.insert(n.id, i);
+        }
+        self.index_node(id);
+    }
+
+    /// Inserts an edge at `index` in the draw order, clamped to the edge count.
+    /// An edge with the same id as an existing one replaces it in place.
+    pub fn insert_edge(&mut self, index: usize, edge: Edge) {
+        if self.edge_index.contains_key(&edge.id) {
+            self.add_edge(edge);
+            return;
+        }
+        self.refresh_index();
+        let id = edge.id;
+        let index = index.min(self.edges.len());
+        self.edges.insert(index, edge);
+        for (i, e) in self.edges.iter().enumerate().skip(index) {
+            self.edge_index.insert(e.id, i);
+        }
+        self.index_edge(id);
+    }
+
+    /// Position of the node in the z-order.
+    pub fn node_index(&self, id: Uuid) -> Option<usize> {
+        self.node_index.get(&id).copied()
+    }
+
+    /// Position of the edge in the draw order.
+    pub fn edge_index(&self, id: Uuid) -> Option<usize> {
+        self.edge_index.get(&id).copied()
     }
 
     pub fn get_node(&self, id: Uuid) -> Option<&Node<D>> {
-        self.nodes.iter().find(|n| n.id == id)
+        self.node_index.get(&id).map(|&index| &self.nodes[index])
+    }
+
+    /// The node's `id` must not be changed: lookups and the spatial index are keyed
+    /// by it. Debug builds check this on the next mutation.
+    pub fn get_node_mut(&mut self, id: Uuid) -> Option<&mut Node<D>> {
+        self.refresh_index();
+        let &index = self.node_index.get(&id)?;
+        // The caller may move or resize the node, so it is re-indexed later.
+        self.spatial.stale_nodes.insert(id);
+        Some(&mut self.nodes[index])
+    }
+
+    pub fn get_edge(&self, id: Uuid) -> Option<&Edge> {
+        self.edge_index.get(&id).map(|&index| &self.edges[index])
+    }
+
+    /// The edge's `id` must not be changed, see `get_node_mut`.
+    pub fn get_edge_mut(&mut self, id: Uuid) -> Option<&mut Edge> {
+        self.refresh_index();
+        let &index = self.edge_index.get(&id)?;
+        self.spatial.stale_edges.insert(id);
+        Some(&mut self.edges[index])
+    }
+
+    pub fn contains_node(&self, id: Uuid) -> bool {
+        self.node_index.contains_key(&id)
+    }
+
+    pub fn contains_edge(&self, id: Uuid) -> bool {
+        self.edge_index.contains_key(&id)
+    }
+
+    /// Removes a node together with every edge connected to it.
+    ///
+    /// Returns the removed node and the edges that were removed with it.
+    pub fn remove_node(&mut self, id: Uuid) -> Option<(Node<D>, Vec<Edge>)> {
+        self.refresh_index();
+        let index = self.node_index.remove(&id)?;
+        let node = self.nodes.remove(index);
+        for (i, n) in self.nodes.iter().enumerate().skip(index) {
+            self.node_index.insert(n.id, i);
+        }
+
+        let connected: Vec<Uuid> = self
+            .edges
+            .iter()
+            .filter(|e| e.source_id == id || e.target_id == id)
+            .map(|e| e.id)
+            .collect();
+        let removed_edges = connected
+            .into_iter()
+            .filter_map(|edge_id| self.remove_edge(edge_id))
+            .collect();
+        self.spatial.nodes.remove(id);
+
+        Some((node, removed_edges))
+    }
+
+    pub fn remove_edge(&mut self, id: Uuid) -> Option<Edge> {
+        self.refresh_index();
+        let index = self.edge_index.remove(&id)?;
+        let edge = self.edges.remove(index);
+        for (i, e) in self.edges.iter().enumerate().skip(index) {
+            self.edge_index.insert(e.id, i);
+        }
+        self.spatial.edges.remove(id);
+        self.spatial.unlink_edge(id);
+        Some(edge)
+    }
+
+    /// Nodes in insertion (z-) order; later nodes are drawn on top.
+    pub fn nodes(&self) -> &[Node<D>] {
+        &self.nodes
+    }
+
+    /// Node ids must not be changed, see `get_node_mut`.
+    pub fn nodes_mut(&mut self) -> impl Iterator<Item = &mut Node<D>> {
+        self.spatial.all_stale = true;
+        self.nodes.iter_mut()
+    }
+
+    /// Edges in insertion order.
+    pub fn edges(&self) -> &[Edge] {
+        &self.edges
+    }
+
+    /// Edge ids must not be changed, see `get_node_mut`.
+    pub fn edges_mut(&mut self) -> impl Iterator<Item = &mut Edge> {
+        self.spatial.all_stale = true;
+        self.edges.iter_mut()
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Nodes whose bounds overlap `area`, in z-order (topmost last).
+    pub fn nodes_in_rect(&self, area: Bounds<f32>) -> Vec<&Node<D>> {
+        let mut indices: Vec<usize> = if self.spatial.all_stale {
+            (0..self.nodes.len()).collect()
+        } else {
+            let stale = &self.spatial.stale_nodes;
+            self.spatial
+                .nodes
+                .query(area)
+                .into_iter()
+                .filter(|id| !stale.contains(id))
+                .chain(stale.iter().copied())
+                .filter_map(|id| self.node_index(id))
+                .collect()
+        };
+        indices.retain(|&index| overlaps(node_bounds(&self.nodes[index]), area));
+        indices.sort_unstable();
+        indices
+            .into_iter()
+            .map(|index| &self.nodes[index])
+            .collect()
+    }
+
+    /// Topmost node containing `position`.
+    pub fn node_at(&self, position: Point<f32>) -> Option<&Node<D>> {
+        self.nodes_in_rect(Bounds::new(position, Size::default()))
+            .pop()
+    }
+
+    /// Handle whose anchor is closest to `position`, if one lies within `radius`.
+    /// Ties go to the topmost node.
+    pub fn nearest_handle(&self, position: Point<f32>, radius: f32) -> Option<(&Node<D>, &Handle)> {
+        // Anchors lie on their node's border, so only nodes overlapping the
+        // search square can have a handle in range.
+        self.nodes_in_rect(search_area(position, radius))
+            .into_iter()
+            .rev()
+            .flat_map(|node| node.handles.iter().map(move |handle| (node, handle)))
+            .map(|(node, handle)| {
+                let anchor = handle_anchor(node, handle);
+                let distance = (anchor.x - position.x).hypot(anchor.y - position.y);
+                (node, handle, distance)
+            })
+            .filter(|&(_, _, distance)| distance <= radius)
+            .min_by(|a, b| a.2.total_cmp(&b.2))
+            .map(|(node, handle, _)| (node, handle))
+    }
+
+    /// Edges whose curve's bounding box overlaps `area`, in insertion order.
+    pub fn edges_in_rect(&self, area: Bounds<f32>) -> Vec<&Edge> {
+        let mut indices: Vec<usize> = if self.spatial.all_stale {
+            (0..self.edges.len()).collect()
+        } else {
+            let spatial = &self.spatial;
+            let stale_edges = spatial
+                .stale_nodes
+                .iter()
+                .filter_map(|id| spatial.node_edges.get(id))
+                .flatten()
+                .chain(&spatial.stale_edges)
+                .copied();
+            spatial
+                .edges
+                .query(area)
+                .into_iter()
+                .filter(|&id| !spatial.is_edge_stale(id))
+                .chain(stale_edges)
+                .filter_map(|id| self.edge_index.get(&id).copied())
+                .collect()
+        };
+        indices.sort_unstable();
+        indices.dedup();
+        indices.retain(|&index| {
+            self.current_edge_bounds(&self.edges[index])
+                .is_some_and(|bounds| overlaps(bounds, area))
+        });
+        indices
+            .into_iter()
+            .map(|index| &self.edges[index])
+            .collect()
+    }
+
+    /// Edge whose curve passes closest to `position`, if one passes within `radius`.
+    /// Ties go to the edge drawn last.
+    pub fn edge_at(&self, position: Point<f32>, radius: f32) -> Option<&Edge> {
+        self.edges_in_rect(search_area(position, radius))
+            .into_iter()
+            .rev()
+            .filter_map(|edge| {
+                let source = self.get_node(edge.source_id)?;
+                let target = self.get_node(edge.target_id)?;
+                let (source_pos, target_pos) = edge_endpoints(edge, source, target);
+                Some((edge, distance_to_edge(position, source_pos, target_pos)))
+            })
+            .filter(|&(_, distance)| distance <= radius)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(edge, _)| edge)
+    }
+
+    fn current_edge_bounds(&self, edge: &Edge) -> Option<Bounds<f32>> {
+        let source = self.get_node(edge.source_id)?;
+        let target = self.get_node(edge.target_id)?;
+        let (source_pos, target_pos) = edge_endpoints(edge, source, target);
+        Some(edge_bounds(source_pos, target_pos))
+    }
+
+    /// Re-indexes everything handed out mutably since the last mutation.
+    fn refresh_index(&mut self) {
+        self.debug_check_ids();
+        if self.spatial.all_stale {
+            self.spatial.clear();
+            let node_ids: Vec<Uuid> = self.nodes.iter().map(|node| node.id).collect();
+            let edge_ids: Vec<Uuid> = self.edges.iter().map(|edge| edge.id).collect();
+            for id in node_ids {
+                self.index_node(id);
+            }
+            for id in edge_ids {
+                self.index_edge(id);
+            }
+            return;
+        }
+        for id in mem::take(&mut self.spatial.stale_nodes) {
+            self.index_node(id);
+        }
+        for id in mem::take(&mut self.spatial.stale_edges) {
+            self.index_edge(id);
+        }
+    }
+
+    /// Panics in debug builds if an id was changed through a `_mut` accessor.
+    fn debug_check_ids(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let node_ok = |id: &Uuid| {
+            self.node_index
+                .get(id)
+                .is_none_or(|&index| self.nodes[index].id == *id)
+        };
+        let edge_ok = |id: &Uuid| {
+            self.edge_index
+                .get(id)
+                .is_none_or(|&index| self.edges[index].id == *id)
+        };
+        if self.spatial.all_stale {
+            assert!(
+                self.nodes.iter().all(|node| node_ok(&node.id))
+                    && self.node_index.keys().all(node_ok),
+                "node id changed through Graph::nodes_mut"
+            );
+            assert!(
+                self.edges.iter().all(|edge| edge_ok(&edge.id))
+                    && self.edge_index.keys().all(edge_ok),
+                "edge id changed through Graph::edges_mut"
+            );
+        } else {
+            assert!(
+                self.spatial.stale_nodes.iter().all(node_ok),
+                "node id changed through Graph::get_node_mut"
+            );
+            assert!(
+                self.spatial.stale_edges.iter().all(edge_ok),
+                "edge id changed through Graph::get_edge_mut"
+            );
+        }
+    }
+
+    /// Indexes node `id` at its current bounds, along with its edges.
+    fn index_node(&mut self, id: Uuid) {
+        self.spatial.stale_nodes.remove(&id);
+        let Some(node) = self.get_node(id) else {
+            self.spatial.nodes.remove(id);
+            return;
+        };
+        let bounds = node_bounds(node);
+        self.spatial.nodes.insert(id, bounds);
+
+        let edge_ids: Vec<Uuid> = self
+            .spatial
+            .node_edges
+            .get(&id)
+            .map(|edges| edges.iter().copied().collect())
+            .unwrap_or_default();
+        for edge_id in edge_ids {
+            self.index_edge(edge_id);
+        }
+    }
+
+    /// Indexes edge `id` at its current curve bounds. Edges whose nodes do not
+    /// exist yet are linked to them but only enter the grid once they do.
+    fn index_edge(&mut self, id: Uuid) {
+        self.spatial.stale_edges.remove(&id);
+        let Some(edge) = self.get_edge(id) else {
+            self.spatial.edges.remove(id);
+            self.spatial.unlink_edge(id);
+            return;
+        };
+        let (source_id, target_id) = (edge.source_id, edge.target_id);
+        let bounds = self.current_edge_bounds(edge);
+
+        self.spatial.link_edge(id, source_id, target_id);
+        match bounds {
+            Some(bounds) => self.spatial.edges.insert(id, bounds),
+            None => self.spatial.edges.remove(id),
+        }
+    }
+}
+
+/// Square of side `2 * radius` centred on `position`.
+fn search_area(position: Point<f32>, radius: f32) -> Bounds<f32> {
+    Bounds::new(
+        position - point(radius, radius),
+        size(radius * 2.0, radius * 2.0),
+    )
+}
+
+#[cfg(feature = "serde")]
+mod serde_impl {
+    use super::{Edge, Graph, Node};
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    // The id -> index maps are derived state, so only the node and edge lists
+    // are written out and the maps are rebuilt on load.
+    #[derive(Serialize)]
+    struct GraphRef<'a, D> {
+        nodes: &'a [Node<D>],
+        edges: &'a [Edge],
+    }
+
+    #[derive(Deserialize)]
+    struct GraphOwned<D> {
+        nodes: Vec<Node<D>>,
+        edges: Vec<Edge>,
+    }
+
+    impl<D: Serialize> Serialize for Graph<D> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            GraphRef {
+                nodes: &self.nodes,
+                edges: &self.edges,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, D: Deserialize<'de>> Deserialize<'de> for Graph<D> {
+        fn deserialize<De: Deserializer<'de>>(deserializer: De) -> Result<Self, De::Error> {
+            let GraphOwned { nodes, edges } = GraphOwned::deserialize(deserializer)?;
+            let mut graph = Graph::new();
+            for node in nodes {
+                graph.add_node(node);
+            }
+            for edge in edges {
+                graph.add_edge(edge);
+            }
+            Ok(graph)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Bounds<f32> {
+        Bounds::new(point(x, y), size(width, height))
+    }
+
+    fn node_ids(nodes: Vec<&Node<()>>) -> Vec<Uuid> {
+        nodes.into_iter().map(|node| node.id).collect()
+    }
+
+    fn edge_ids(edges: Vec<&Edge>) -> Vec<Uuid> {
+        edges.into_iter().map(|edge| edge.id).collect()
+    }
+
+    /// Two default-sized nodes several cells apart, joined by an edge.
+    fn linked_pair() -> (Graph<()>, Uuid, Uuid, Uuid) {
+        let mut graph = Graph::new();
+        let a = Node::new((), point(0.0, 0.0));
+        let b = Node::new((), point(1000.0, 1000.0));
+        let (a_id, b_id) = (a.id, b.id);
+        graph.add_node(a);
+        graph.add_node(b);
+        let edge = Edge::new(a_id, b_id);
+        let edge_id = edge.id;
+        graph.add_edge(edge);
+        (graph, a_id, b_id, edge_id)
+    }
+
+    #[test]
+    fn queries_follow_get_node_mut() {
+        let (mut graph, a, _, edge) = linked_pair();
+        graph.get_node_mut(a).unwrap().position = point(3000.0, 0.0);
+
+        // Before and after the next mutation re-indexes the node.
+        for _ in 0..2 {
+            assert!(graph.nodes_in_rect(rect(0.0, 0.0, 10.0, 10.0)).is_empty());
+            assert_eq!(
+                node_ids(graph.nodes_in_rect(rect(3000.0, 0.0, 10.0, 10.0))),
+                [a]
+            );
+            // The edge now runs from (3075, 80) to (1075, 1000).
+            assert!(graph.edges_in_rect(rect(0.0, 0.0, 500.0, 500.0)).is_empty());
+            assert_eq!(
+                edge_ids(graph.edges_in_rect(rect(2500.0, 500.0, 10.0, 10.0))),
+                [edge]
+            );
+            graph.add_node(Node::new((), point(-5000.0, -5000.0)));
+        }
+    }
+
+    #[test]
+    fn queries_follow_nodes_mut() {
+        let (mut graph, a, b, edge) = linked_pair();
+        for node in graph.nodes_mut() {
+            node.position.x += 5000.0;
+        }
+
+        for _ in 0..2 {
+            assert!(
+                graph
+                    .nodes_in_rect(rect(0.0, 0.0, 2000.0, 2000.0))
+                    .is_empty()
+            );
+            assert!(
+                graph
+                    .edges_in_rect(rect(0.0, 0.0, 2000.0, 2000.0))
+                    .is_empty()
+            );
+            assert_eq!(
+                node_ids(graph.nodes_in_rect(rect(5000.0, 0.0, 2000.0, 2000.0))),
+                [a, b]
+            );
+            assert_eq!(
+                edge_ids(graph.edges_in_rect(rect(5500.0, 500.0, 10.0, 10.0))),
+                [edge]
+            );
+            graph.add_node(Node::new((), point(-5000.0, -5000.0)));
+        }
+    }
+
+    #[test]
+    fn removed_nodes_and_their_edges_leave_the_index() {
+        let (mut graph, a, b, _) = linked_pair();
+        graph.remove_node(a);
+
+        let everything = rect(-10_000.0, -10_000.0, 20_000.0, 20_000.0);
+        assert_eq!(node_ids(graph.nodes_in_rect(everything)), [b]);
+        assert!(graph.edges_in_rect(everything).is_empty());
+        assert!(graph.node_at(point(10.0, 10.0)).is_none());
+    }
+
+    #[test]
+    fn entries_spanning_several_cells_are_found_once() {
+        let (mut graph, a, _, edge) = linked_pair();
+        let big = Node::new((), point(-600.0, -600.0)).with_size(2000.0, 2000.0);
+        let big_id = big.id;
+        graph.add_node(big);
+
+        // Far from the big node's first cell, in each of its corners.
+        for corner in [
+            point(-590.0, 1390.0),
+            point(1390.0, -590.0),
+            point(1390.0, 1390.0),
+        ] {
+            assert_eq!(graph.node_at(corner).map(|node| node.id), Some(big_id));
+        }
+        // Areas covering many of its cells still report it once, in z-order.
+        let everything = rect(-10_000.0, -10_000.0, 20_000.0, 20_000.0);
+        let ids = node_ids(graph.nodes_in_rect(everything));
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[0], a);
+        assert_eq!(ids[2], big_id);
+        assert_eq!(
+            node_ids(graph.nodes_in_rect(rect(500.0, 500.0, 600.0, 600.0))).len(),
+            2
+        );
+
+        // The edge's bounds span cells from (75, 80) to (1075, 1000).
+        assert_eq!(edge_ids(graph.edges_in_rect(everything)), [edge]);
+        assert_eq!(
+            edge_ids(graph.edges_in_rect(rect(800.0, 800.0, 10.0, 10.0))),
+            [edge]
+        );
     }
 }
@@ -0,0 +1,507 @@
+use crate::changes::{EdgeChange, NodeChange};
+use crate::graph::{Edge, Graph, Node};
+use gpui::Point;
+use std::collections::VecDeque;
+use uuid::Uuid;
+
+/// An invertible graph mutation.
+#[derive(Clone, Debug)]
+pub enum Command<D> {
+    MoveNode {
+        id: Uuid,
+        from: Point<f32>,
+        to: Point<f32>,
+    },
+    AddNode {
+        node: Node<D>,
+    },
+    RemoveNode {
+        /// Index in the node list, so undo restores the original z-order.
+        index: usize,
+        node: Node<D>,
+        /// Edges that were removed together with the node, with their indices in
+        /// the edge list, in ascending order.
+        edges: Vec<(usize, Edge)>,
+    },
+    ReplaceNode {
+        before: Node<D>,
+        after: Node<D>,
+    },
+    /// An edit of the node's data only; position, size and selection are untouched.
+    EditNodeData {
+        id: Uuid,
+        before: D,
+        after: D,
+    },
+    AddEdge {
+        edge: Edge,
+    },
+    RemoveEdge {
+        /// Index in the edge list, so undo restores the original draw order.
+        index: usize,
+        edge: Edge,
+    },
+    ReplaceEdge {
+        before: Edge,
+        after: Edge,
+    },
+    /// Several commands undone and redone as one step.
+    Batch(Vec<Command<D>>),
+}
+
+/// A node or edge change produced when replaying a `Command`.
+#[derive(Clone, Debug)]
+pub(crate) enum GraphChange<D> {
+    Node(NodeChange<D>),
+    Edge(EdgeChange),
+}
+
+impl<D: Clone> Command<D> {
+    /// Applies the command to `graph`.
+    pub fn apply(&self, graph: &mut Graph<D>) {
+        graph.apply_changes(self.apply_changes());
+    }
+
+    /// Reverts the command on `graph`, which must be in the state `apply` left it in.
+    pub fn revert(&self, graph: &mut Graph<D>) {
+        graph.apply_changes(self.revert_changes());
+    }
+
+    /// The changes that apply the command, in order.
+    pub(crate) fn apply_changes(&self) -> Vec<GraphChange<D>> {
+        let mut changes = Vec::new();
+        self.push_changes(false, &mut changes);
+        changes
+    }
+
+    /// The changes that revert the command, in order.
+    pub(crate) fn revert_changes(&self) -> Vec<GraphChange<D>> {
+        let mut changes = Vec::new();
+        self.push_changes(true, &mut changes);
+        changes
+    }
+
+    fn push_changes(&self, revert: bool, changes: &mut Vec<GraphChange<D>>) {
+        let node = GraphChange::Node;
+        let edge = GraphChange::Edge;
+        match self {
+            Command::MoveNode { id, from, to } => changes.push(node(NodeChange::Position {
+                id: *id,
+                position: if revert { *from } else { *to },
+                dragging: false,
+            })),
+            Command::AddNode { node: added } => changes.push(node(if revert {
+                NodeChange::Remove { id: added.id }
+            } else {
+                NodeChange::Add {
+                    node: added.clone(),
+                }
+            })),
+            Command::RemoveNode {
+                index,
+                node: removed,
+                edges,
+            } => {
+                if revert {
+                    changes.push(node(NodeChange::Insert {
+                        index: *index,
+                        node: removed.clone(),
+                    }));
+                    changes.extend(edges.iter().map(|(index, e)| {
+                        edge(EdgeChange::Insert {
+                            index: *index,
+                            edge: e.clone(),
+                        })
+                    }));
+                } else {
+                    changes.extend(
+                        edges
+                            .iter()
+                            .map(|(_, e)| edge(EdgeChange::Remove { id: e.id })),
+                    );
+                    changes.push(node(NodeChange::Remove { id: removed.id }));
+                }
+            }
+            Command::ReplaceNode { before, after } => changes.push(node(NodeChange::Replace {
+                node: (if revert { before } else { after }).clone(),
+            })),
+            Command::EditNodeData { id, before, after } => changes.push(node(NodeChange::Data {
+                id: *id,
+                data: (if revert { before } else { after }).clone(),
+            })),
+            Command::AddEdge { edge: added } => changes.push(edge(if revert {
+                EdgeChange::Remove { id: added.id }
+            } else {
+                EdgeChange::Add {
+                    edge: added.clone(),
+                }
+            })),
+            Command::RemoveEdge {
+                index,
+                edge: removed,
+            } => changes.push(edge(if revert {
+                EdgeChange::Insert {
+                    index: *index,
+                    edge: removed.clone(),
+                }
+            } else {
+                EdgeChange::Remove { id: removed.id }
+            })),
+            Command::ReplaceEdge { before, after } => changes.push(edge(EdgeChange::Replace {
+                edge: (if revert { before } else { after }).clone(),
+            })),
+            Command::Batch(commands) => {
+                if revert {
+                    for command in commands.iter().rev() {
+                        command.push_changes(true, changes);
+                    }
+                } else {
+                    for command in commands {
+                        command.push_changes(false, changes);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<D> Graph<D> {
+    fn apply_changes(&mut self, changes: Vec<GraphChange<D>>) {
+        for change in changes {
+            match change {
+                GraphChange::Node(change) => self.apply_node_changes([change]),
+                GraphChange::Edge(change) => self.apply_edge_changes([change]),
+            }
+        }
+    }
+}
+
+/// Undo/redo stacks of `Command`s.
+///
+/// Commands are recorded after they have been applied. Recording a new command
+/// clears the redo stack. Commands recorded between `begin_transaction` and
+/// `commit_transaction` are grouped into a single `Command::Batch`.
+#[derive(Clone, Debug)]
+pub struct History<D> {
+    undo_stack: VecDeque<Command<D>>,
+    redo_stack: Vec<Command<D>>,
+    max_depth: usize,
+    transaction: Option<Vec<Command<D>>>,
+    transaction_depth: usize,
+}
+
+impl<D> Default for History<D> {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl<D> History<D> {
+    /// Creates a history that keeps at most `max_depth` undo steps.
+    pub fn new(max_depth: usize) -> Self {
+        Self {
+            undo_stack: VecDeque::new(),
+            redo_stack: Vec::new(),
+            max_depth,
+            transaction: None,
+            transaction_depth: 0,
+        }
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+        while self.undo_stack.len() > max_depth {
+            self.undo_stack.pop_front();
+        }
+    }
+
+    /// Records a command that has already been applied to the graph.
+    pub fn record(&mut self, command: Command<D>) {
+        if let Some(transaction) = &mut self.transaction {
+            transaction.push(command);
+            return;
+        }
+        self.push(command);
+    }
+
+    fn push(&mut self, command: Command<D>) {
+        self.redo_stack.clear();
+        if self.max_depth == 0 {
+            return;
+        }
+        if self.undo_stack.len() == self.max_depth {
+            self.undo_stack.pop_front();
+        }
+        self.undo_stack.push_back(command);
+    }
+
+    /// Starts grouping recorded commands into one undo step. Transactions nest;
+    /// only the outermost `commit_transaction` produces the step.
+    pub fn begin_transaction(&mut self) {
+        self.transaction_depth += 1;
+        self.transaction.get_or_insert_with(Vec::new);
+    }
+
+    pub fn commit_transaction(&mut self) {
+        if self.transaction_depth == 0 {
+            return;
+        }
+        self.transaction_depth -= 1;
+        if self.transaction_depth > 0 {
+            return;
+        }
+        if let Some(mut commands) = self.transaction.take() {
+            match commands.len() {
+                0 => {}
+                1 => self.push(commands.remove(0)),
+                _ => self.push(Command::Batch(commands)),
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo_stack.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo_stack.is_empty()
+    }
+
+    /// Moves the most recent step to the redo stack and returns it, for callers
+    /// that revert it themselves, e.g. through `Command::revert_changes`.
+    pub fn pop_undo(&mut self) -> Option<&Command<D>> {
+        let command = self.undo_stack.pop_back()?;
+        self.redo_stack.push(command);
+        self.redo_stack.last()
+    }
+
+    /// Moves the most recently undone step back to the undo stack and returns it,
+    /// for callers that re-apply it themselves.
+    pub fn pop_redo(&mut self) -> Option<&Command<D>> {
+        let command = self.redo_stack.pop()?;
+        self.undo_stack.push_back(command);
+        self.undo_stack.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo_stack.clear();
+        self.redo_stack.clear();
+        self.transaction = None;
+        self.transaction_depth = 0;
+    }
+}
+
+impl<D: Clone> History<D> {
+    /// Reverts the most recent step. Returns `false` if there was nothing to undo.
+    pub fn undo(&mut self, graph: &mut Graph<D>) -> bool {
+        let Some(command) = self.pop_undo() else {
+            return false;
+        };
+        command.revert(graph);
+        true
+    }
+
+    /// Re-applies the most recently undone step. Returns `false` if there was nothing to redo.
+    pub fn redo(&mut self, graph: &mut Graph<D>) -> bool {
+        let Some(command) = self.pop_redo() else {
+            return false;
+        };
+        command.apply(graph);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::point;
+
+    fn node_ids(graph: &Graph<&'static str>) -> Vec<Uuid> {
+        graph.nodes().iter().map(|node| node.id).collect()
+    }
+
+    fn edge_ids(graph: &Graph<&'static str>) -> Vec<Uuid> {
+        graph.edges().iter().map(|edge| edge.id).collect()
+    }
+
+    #[test]
+    fn undo_and_redo_move() {
+        let mut graph = Graph::new();
+        let node = Node::new("a", point(0.0, 0.0));
+        let id = node.id;
+        graph.add_node(node);
+
+        let mut history = History::default();
+        graph.get_node_mut(id).unwrap().position = point(10.0, 20.0);
+        history.record(Command::MoveNode {
+            id,
+            from: point(0.0, 0.0),
+            to: point(10.0, 20.0),
+        });
+
+        assert!(history.undo(&mut graph));
+        assert_eq!(graph.get_node(id).unwrap().position, point(0.0, 0.0));
+        assert!(!history.can_undo());
+        assert!(history.redo(&mut graph));
+        assert_eq!(graph.get_node(id).unwrap().position, point(10.0, 20.0));
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn undoing_node_removal_restores_order_and_edges() {
+        let mut graph = Graph::new();
+        let nodes: Vec<_> = ["a", "b", "c"]
+            .into_iter()
+            .map(|data| Node::new(data, point(0.0, 0.0)))
+            .collect();
+        let ids: Vec<_> = nodes.iter().map(|node| node.id).collect();
+        for node in nodes {
+            graph.add_node(node);
+        }
+        // The edge between the other two nodes sits between the removed ones.
+        for (source, target) in [(0, 1), (0, 2), (1, 2)] {
+            graph.add_edge(Edge::new(ids[source], ids[target]));
+        }
+        let edges_before = edge_ids(&graph);
+
+        let mut history = History::default();
+        let index = graph.node_index(ids[1]).unwrap();
+        let (node, edges) = graph.remove_node(ids[1]).unwrap();
+        let edges = [0, 2].into_iter().zip(edges).collect();
+        history.record(Command::RemoveNode { index, node, edges });
+        assert_eq!(graph.edge_count(), 1);
+
+        history.undo(&mut graph);
+        assert_eq!(node_ids(&graph), ids);
+        assert_eq!(edge_ids(&graph), edges_before);
+
+        history.redo(&mut graph);
+        assert_eq!(node_ids(&graph), [ids[0], ids[2]]);
+        assert_eq!(edge_ids(&graph), [edges_before[1]]);
+    }
+
+    #[test]
+    fn undoing_edge_removal_restores_draw_order() {
+        let mut graph = Graph::new();
+        let a = Node::new("a", point(0.0, 0.0));
+        let b = Node::new("b", point(0.0, 0.0));
+        let edges: Vec<_> = (0..3).map(|_| Edge::new(a.id, b.id)).collect();
+        graph.add_node(a);
+        graph.add_node(b);
+        for edge in edges {
+            graph.add_edge(edge);
+        }
+        let ids = edge_ids(&graph);
+
+        let mut history = History::default();
+        let edge = graph.remove_edge(ids[1]).unwrap();
+        history.record(Command::RemoveEdge { index: 1, edge });
+        history.undo(&mut graph);
+        assert_eq!(edge_ids(&graph), ids);
+    }
+
+    #[test]
+    fn data_edit_leaves_other_fields_alone() {
+        let mut graph = Graph::new();
+        let node = Node::new("before", point(0.0, 0.0));
+        let id = node.id;
+        graph.add_node(node);
+
+        let mut history = History::default();
+        graph.get_node_mut(id).unwrap().data = "after";
+        history.record(Command::EditNodeData {
+            id,
+            before: "before",
+            after: "after",
+        });
+        let node = graph.get_node_mut(id).unwrap();
+        node.selected = true;
+        node.position = point(5.0, 5.0);
+
+        history.undo(&mut graph);
+        let node = graph.get_node(id).unwrap();
+        assert_eq!(node.data, "before");
+        assert!(node.selected);
+        assert_eq!(node.position, point(5.0, 5.0));
+    }
+
+    #[test]
+    fn new_command_clears_redo() {
+        let mut graph: Graph<&str> = Graph::new();
+        let mut history = History::default();
+        let node = Node::new("a", point(0.0, 0.0));
+        graph.add_node(node.clone());
+        history.record(Command::AddNode { node });
+        history.undo(&mut graph);
+        assert!(history.can_redo());
+
+        let node = Node::new("b", point(0.0, 0.0));
+        graph.add_node(node.clone());
+        history.record(Command::AddNode { node });
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn transactions_nest_into_one_step() {
+        let mut graph = Graph::new();
+        let mut history = History::default();
+        history.begin_transaction();
+        for data in ["a", "b"] {
+            history.begin_transaction();
+            let node = Node::new(data, point(0.0, 0.0));
+            graph.add_node(node.clone());
+            history.record(Command::AddNode { node });
+            history.commit_transaction();
+        }
+        assert!(!history.can_undo());
+        history.commit_transaction();
+
+        assert!(history.undo(&mut graph));
+        assert_eq!(graph.node_count(), 0);
+        assert!(!history.can_undo());
+        history.redo(&mut graph);
+        assert_eq!(graph.node_count(), 2);
+    }
+
+    #[test]
+    fn max_depth_drops_oldest_steps() {
+        let mut graph = Graph::new();
+        let mut history = History::new(2);
+        for data in ["a", "b", "c"] {
+            let node = Node::new(data, point(0.0, 0.0));
+            graph.add_node(node.clone());
+            history.record(Command::AddNode { node });
+        }
+
+        assert!(history.undo(&mut graph));
+        assert!(history.undo(&mut graph));
+        assert!(!history.undo(&mut graph));
+        assert_eq!(graph.node_count(), 1);
+        assert_eq!(graph.nodes()[0].data, "a");
+    }
+
+    #[test]
+    fn pop_undo_moves_the_step_without_touching_the_graph() {
+        let mut history: History<()> = History::default();
+        let id = Uuid::new_v4();
+        history.record(Command::MoveNode {
+            id,
+            from: point(0.0, 0.0),
+            to: point(1.0, 1.0),
+        });
+
+        let changes = history.pop_undo().unwrap().revert_changes();
+        assert!(matches!(
+            changes.as_slice(),
+            [GraphChange::Node(NodeChange::Position { position, dragging: false, .. })]
+                if *position == point(0.0, 0.0)
+        ));
+        assert!(history.can_redo());
+        assert!(history.pop_redo().is_some());
+        assert!(history.can_undo());
+    }
+}
@@ -1,9 +1,31 @@
+pub mod changes;
 pub mod components;
+#[cfg(feature = "serde")]
+pub mod document;
+pub mod events;
+pub mod geometry;
 pub mod graph;
+pub mod history;
+mod spatial;
 pub mod types;
+pub mod validation;
 pub mod view;
+pub mod viewport;
 
-pub use components::background::{BackgroundProps, BackgroundVariant, render_background};
-pub use graph::{Edge, Graph, Handle, HandleType, Node};
+pub use changes::{EdgeChange, NodeChange};
+pub use components::background::{
+    Background, BackgroundCache, BackgroundPainter, BackgroundProps, BackgroundVariant,
+    PatternQuad, PatternQuads, pattern_quads, render_background,
+};
+pub use components::edge::{EdgeStroke, EdgeStyle};
+pub use components::node::NodeRenderContext;
+#[cfg(feature = "serde")]
+pub use document::{DOCUMENT_VERSION, DocumentError, FlowDocument};
+pub use events::GraphEvent;
+pub use graph::{Connection, DEFAULT_NODE_SIZE, Edge, Graph, Handle, HandleType, Node};
+pub use history::{Command, History};
+pub use types::port::{Multiplicity, PortCompatibility, PortType};
 pub use types::position::Position;
-pub use view::GraphView;
+pub use validation::{ConnectionError, ConnectionMode, ConnectionRules};
+pub use view::{GraphView, SelectionKey, SelectionMode};
+pub use viewport::{Transition, Viewport};
@@ -0,0 +1,246 @@
+//! Uniform grid that `Graph` uses to find nodes and edges by area.
+//!
+//! Entries are bucketed into square cells, so looking up a small area only
+//! touches the few entries that share its cells. Moving an entry removes it from
+//! each of its old cells and adds it to each new one, so entries spanning many
+//! cells (such as long edges) are kept in a separate list that every query
+//! checks instead.
+
+use gpui::Bounds;
+use std::collections::{HashMap, HashSet};
+use uuid::Uuid;
+
+/// Side of a grid cell in flow units; roughly a couple of typical nodes across.
+const CELL_SIZE: f32 = 256.0;
+
+/// Entries covering more cells than this are not bucketed.
+const MAX_ENTRY_CELLS: u64 = 16;
+
+type Cell = (i32, i32);
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Grid {
+    cells: HashMap<Cell, Vec<Uuid>>,
+    entries: HashMap<Uuid, Bounds<f32>>,
+    /// Entries covering more than `MAX_ENTRY_CELLS` cells, tested by every query.
+    large: HashSet<Uuid>,
+}
+
+impl Grid {
+    /// Inserts `id` with `bounds`, replacing any previous entry for it.
+    pub fn insert(&mut self, id: Uuid, bounds: Bounds<f32>) {
+        if self.entries.get(&id) == Some(&bounds) {
+            return;
+        }
+        self.remove(id);
+        self.entries.insert(id, bounds);
+        let (min, max) = cell_range(bounds);
+        if cell_count(min, max) > MAX_ENTRY_CELLS {
+            self.large.insert(id);
+            return;
+        }
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                self.cells.entry((x, y)).or_default().push(id);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, id: Uuid) {
+        let Some(bounds) = self.entries.remove(&id) else {
+            return;
+        };
+        if self.large.remove(&id) {
+            return;
+        }
+        let (min, max) = cell_range(bounds);
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                if let Some(ids) = self.cells.get_mut(&(x, y)) {
+                    ids.retain(|&other| other != id);
+                    if ids.is_empty() {
+                        self.cells.remove(&(x, y));
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entries.clear();
+        self.large.clear();
+    }
+
+    /// Ids whose bounds overlap `area`, each reported once, in no particular order.
+    pub fn query(&self, area: Bounds<f32>) -> Vec<Uuid> {
+        let (min, max) = cell_range(area);
+        let in_range =
+            |cell: Cell| (min.0..=max.0).contains(&cell.0) && (min.1..=max.1).contains(&cell.1);
+        // An entry spanning several cells is reported from the first cell it
+        // shares with the area only.
+        let report = |cell: Cell, id: Uuid| {
+            let bounds = self.entries[&id];
+            let (entry_min, _) = cell_range(bounds);
+            cell == (entry_min.0.max(min.0), entry_min.1.max(min.1)) && overlaps(bounds, area)
+        };
+
+        let large = self
+            .large
+            .iter()
+            .filter(|&&id| overlaps(self.entries[&id], area))
+            .copied();
+        if cell_count(min, max) > self.cells.len() as u64 {
+            // Areas covering more cells than are occupied are cheaper to answer
+            // by walking the occupied cells.
+            self.cells
+                .iter()
+                .filter(|(cell, _)| in_range(**cell))
+                .flat_map(|(&cell, ids)| ids.iter().filter(move |id| report(cell, **id)))
+                .copied()
+                .chain(large)
+                .collect()
+        } else {
+            (min.0..=max.0)
+                .flat_map(|x| (min.1..=max.1).map(move |y| (x, y)))
+                .filter_map(|cell| Some((cell, self.cells.get(&cell)?)))
+                .flat_map(|(cell, ids)| ids.iter().filter(move |id| report(cell, **id)))
+                .copied()
+                .chain(large)
+                .collect()
+        }
+    }
+}
+
+/// Whether `a` and `b` overlap or touch.
+pub(crate) fn overlaps(a: Bounds<f32>, b: Bounds<f32>) -> bool {
+    a.origin.x <= b.origin.x + b.size.width
+        && b.origin.x <= a.origin.x + a.size.width
+        && a.origin.y <= b.origin.y + b.size.height
+        && b.origin.y <= a.origin.y + a.size.height
+}
+
+fn cell_count(min: Cell, max: Cell) -> u64 {
+    let columns = (max.0 as i64 - min.0 as i64 + 1) as u64;
+    let rows = (max.1 as i64 - min.1 as i64 + 1) as u64;
+    columns.saturating_mul(rows)
+}
+
+fn cell_range(bounds: Bounds<f32>) -> (Cell, Cell) {
+    let bottom_right = bounds.bottom_right();
+    (
+        (
+            (bounds.origin.x / CELL_SIZE).floor() as i32,
+            (bounds.origin.y / CELL_SIZE).floor() as i32,
+        ),
+        (
+            (bottom_right.x / CELL_SIZE).floor() as i32,
+            (bottom_right.y / CELL_SIZE).floor() as i32,
+        ),
+    )
+}
+
+/// Spatial bookkeeping kept by `Graph` alongside its nodes and edges.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct SpatialIndex {
+    /// Node bounds.
+    pub nodes: Grid,
+    /// Bounds of each edge's curve.
+    pub edges: Grid,
+    /// Edges attached to each node id, so edges follow their nodes when re-indexed.
+    pub node_edges: HashMap<Uuid, HashSet<Uuid>>,
+    /// Endpoints each edge was last linked with in `node_edges`.
+    pub edge_ends: HashMap<Uuid, (Uuid, Uuid)>,
+    /// Nodes and edges handed out mutably since they were last indexed. Queries
+    /// test them directly until the next graph mutation re-indexes them.
+    pub stale_nodes: HashSet<Uuid>,
+    pub stale_edges: HashSet<Uuid>,
+    /// Set by `Graph::nodes_mut` and `Graph::edges_mut`: anything may be stale.
+    pub all_stale: bool,
+}
+
+impl SpatialIndex {
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.edges.clear();
+        self.node_edges.clear();
+        self.edge_ends.clear();
+        self.stale_nodes.clear();
+        self.stale_edges.clear();
+        self.all_stale = false;
+    }
+
+    pub fn link_edge(&mut self, id: Uuid, source_id: Uuid, target_id: Uuid) {
+        self.unlink_edge(id);
+        self.node_edges.entry(source_id).or_default().insert(id);
+        self.node_edges.entry(target_id).or_default().insert(id);
+        self.edge_ends.insert(id, (source_id, target_id));
+    }
+
+    pub fn unlink_edge(&mut self, id: Uuid) {
+        let Some((source_id, target_id)) = self.edge_ends.remove(&id) else {
+            return;
+        };
+        for node_id in [source_id, target_id] {
+            if let Some(edges) = self.node_edges.get_mut(&node_id) {
+                edges.remove(&id);
+                if edges.is_empty() {
+                    self.node_edges.remove(&node_id);
+                }
+            }
+        }
+    }
+
+    /// Whether the indexed bounds of edge `id` may be out of date.
+    pub fn is_edge_stale(&self, id: Uuid) -> bool {
+        self.stale_edges.contains(&id)
+            || self
+                .edge_ends
+                .get(&id)
+                .is_none_or(|(source_id, target_id)| {
+                    self.stale_nodes.contains(source_id) || self.stale_nodes.contains(target_id)
+                })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{point, size};
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> Bounds<f32> {
+        Bounds::new(point(x, y), size(width, height))
+    }
+
+    #[test]
+    fn entries_spanning_cells_are_reported_once() {
+        let mut grid = Grid::default();
+        let id = Uuid::new_v4();
+        // 4x4 cells: bucketed.
+        grid.insert(id, rect(-300.0, -300.0, 600.0, 600.0));
+        assert!(grid.large.is_empty());
+
+        assert_eq!(grid.query(rect(250.0, 250.0, 1.0, 1.0)), [id]);
+        assert_eq!(grid.query(rect(-1000.0, -1000.0, 2000.0, 2000.0)), [id]);
+        assert_eq!(grid.query(rect(-100.0, -100.0, 200.0, 200.0)), [id]);
+        assert!(grid.query(rect(400.0, 400.0, 10.0, 10.0)).is_empty());
+    }
+
+    #[test]
+    fn large_entries_skip_the_cells() {
+        let mut grid = Grid::default();
+        let id = Uuid::new_v4();
+        grid.insert(id, rect(0.0, 0.0, 5000.0, 100.0));
+        assert!(grid.cells.is_empty());
+        assert_eq!(grid.query(rect(4000.0, 50.0, 1.0, 1.0)), [id]);
+        assert!(grid.query(rect(4000.0, 500.0, 1.0, 1.0)).is_empty());
+
+        // Shrinking moves it into the cells, and removing clears both.
+        grid.insert(id, rect(0.0, 0.0, 100.0, 100.0));
+        assert!(grid.large.is_empty());
+        assert_eq!(grid.query(rect(50.0, 50.0, 1.0, 1.0)), [id]);
+        grid.insert(id, rect(0.0, 0.0, 5000.0, 100.0));
+        grid.remove(id);
+        assert!(grid.cells.is_empty() && grid.large.is_empty() && grid.entries.is_empty());
+    }
+}
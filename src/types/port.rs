@@ -0,0 +1,102 @@
+use gpui::{Hsla, rgb};
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+
+/// Data type carried by a handle, e.g. `float` or `texture`.
+///
+/// Port types are identified by name; the colour is only used to draw handles
+/// and edges of that type.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PortType {
+    pub name: String,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::components::background::hsla_serde")
+    )]
+    pub color: Hsla,
+}
+
+impl PortType {
+    pub fn custom(name: impl Into<String>, color: impl Into<Hsla>) -> Self {
+        Self {
+            name: name.into(),
+            color: color.into(),
+        }
+    }
+
+    pub fn float() -> Self {
+        Self::custom("float", rgb(0x8bc34a))
+    }
+
+    pub fn vec2() -> Self {
+        Self::custom("vec2", rgb(0x4dd0e1))
+    }
+
+    pub fn vec3() -> Self {
+        Self::custom("vec3", rgb(0xffca28))
+    }
+
+    pub fn vec4() -> Self {
+        Self::custom("vec4", rgb(0xba68c8))
+    }
+
+    pub fn texture() -> Self {
+        Self::custom("texture", rgb(0xef5350))
+    }
+}
+
+impl PartialEq for PortType {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+
+impl Eq for PortType {}
+
+impl Hash for PortType {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.name.hash(state);
+    }
+}
+
+/// How many edges may be attached to a handle.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Multiplicity {
+    Single,
+    #[default]
+    Many,
+}
+
+/// Which port types may be connected to each other.
+///
+/// Identical types are always compatible; anything else needs an explicit
+/// coercion from the source type to the target type.
+#[derive(Clone, Debug, Default)]
+pub struct PortCompatibility {
+    coercions: HashSet<(String, String)>,
+}
+
+impl PortCompatibility {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allows a `from` output to feed a `to` input.
+    pub fn allow(&mut self, from: &PortType, to: &PortType) {
+        self.coercions.insert((from.name.clone(), to.name.clone()));
+    }
+
+    pub fn with_coercion(mut self, from: &PortType, to: &PortType) -> Self {
+        self.allow(from, to);
+        self
+    }
+
+    pub fn is_compatible(&self, from: &PortType, to: &PortType) -> bool {
+        from == to
+            || self
+                .coercions
+                .contains(&(from.name.clone(), to.name.clone()))
+    }
+}
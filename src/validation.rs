@@ -0,0 +1,475 @@
+use crate::components::handle::Handle;
+use crate::graph::{Connection, Edge, Graph, Node};
+use crate::types::handle::HandleType;
+use crate::types::port::{Multiplicity, PortCompatibility, PortType};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+use uuid::Uuid;
+
+type IsValidConnectionFn<D> = Arc<dyn Fn(&Connection, &Graph<D>) -> bool + Send + Sync>;
+
+/// Which handle types may be joined by an edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectionMode {
+    /// Edges must run from a `HandleType::Source` handle to a `HandleType::Target` handle.
+    #[default]
+    Strict,
+    /// Any two handles may be connected.
+    Loose,
+}
+
+/// Rules a `Connection` must satisfy before it becomes an `Edge`.
+pub struct ConnectionRules<D> {
+    pub mode: ConnectionMode,
+    pub allow_self_loops: bool,
+    pub allow_duplicates: bool,
+    pub prevent_cycles: bool,
+    pub port_compatibility: PortCompatibility,
+    is_valid_connection: Option<IsValidConnectionFn<D>>,
+}
+
+impl<D> Clone for ConnectionRules<D> {
+    fn clone(&self) -> Self {
+        Self {
+            mode: self.mode,
+            allow_self_loops: self.allow_self_loops,
+            allow_duplicates: self.allow_duplicates,
+            prevent_cycles: self.prevent_cycles,
+            port_compatibility: self.port_compatibility.clone(),
+            is_valid_connection: self.is_valid_connection.clone(),
+        }
+    }
+}
+
+impl<D> Default for ConnectionRules<D> {
+    fn default() -> Self {
+        Self {
+            mode: ConnectionMode::Strict,
+            allow_self_loops: false,
+            allow_duplicates: false,
+            prevent_cycles: false,
+            port_compatibility: PortCompatibility::default(),
+            is_valid_connection: None,
+        }
+    }
+}
+
+impl<D> fmt::Debug for ConnectionRules<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionRules")
+            .field("mode", &self.mode)
+            .field("allow_self_loops", &self.allow_self_loops)
+            .field("allow_duplicates", &self.allow_duplicates)
+            .field("prevent_cycles", &self.prevent_cycles)
+            .field("port_compatibility", &self.port_compatibility)
+            .field("is_valid_connection", &self.is_valid_connection.is_some())
+            .finish()
+    }
+}
+
+impl<D> ConnectionRules<D> {
+    /// Rules that accept any connection between existing nodes and handles.
+    pub fn permissive() -> Self {
+        Self {
+            mode: ConnectionMode::Loose,
+            allow_self_loops: true,
+            allow_duplicates: true,
+            prevent_cycles: false,
+            port_compatibility: PortCompatibility::default(),
+            is_valid_connection: None,
+        }
+    }
+
+    pub fn with_mode(mut self, mode: ConnectionMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_self_loops(mut self, allow: bool) -> Self {
+        self.allow_self_loops = allow;
+        self
+    }
+
+    pub fn with_duplicates(mut self, allow: bool) -> Self {
+        self.allow_duplicates = allow;
+        self
+    }
+
+    pub fn with_cycle_prevention(mut self, prevent: bool) -> Self {
+        self.prevent_cycles = prevent;
+        self
+    }
+
+    /// Table deciding which port types may feed each other.
+    pub fn with_port_compatibility(mut self, port_compatibility: PortCompatibility) -> Self {
+        self.port_compatibility = port_compatibility;
+        self
+    }
+
+    /// Adds a user check that runs after the built-in rules have passed.
+    pub fn with_validator<F>(mut self, is_valid_connection: F) -> Self
+    where
+        F: Fn(&Connection, &Graph<D>) -> bool + Send + Sync + 'static,
+    {
+        self.is_valid_connection = Some(Arc::new(is_valid_connection));
+        self
+    }
+
+    pub fn validate(
+        &self,
+        connection: &Connection,
+        graph: &Graph<D>,
+    ) -> Result<(), ConnectionError> {
+        let source = graph
+            .get_node(connection.source_id)
+            .ok_or(ConnectionError::MissingNode(connection.source_id))?;
+        let target = graph
+            .get_node(connection.target_id)
+            .ok_or(ConnectionError::MissingNode(connection.target_id))?;
+
+        let source_handle = find_handle(source, &connection.source_handle_id)?;
+        let target_handle = find_handle(target, &connection.target_handle_id)?;
+
+        if self.mode == ConnectionMode::Strict {
+            let source_ok = source_handle.is_none_or(|h| h.handle_type == HandleType::Source);
+            let target_ok = target_handle.is_none_or(|h| h.handle_type == HandleType::Target);
+            if !source_ok || !target_ok {
+                return Err(ConnectionError::InvalidHandleType);
+            }
+        }
+
+        if let (Some(source_port), Some(target_port)) = (
+            source_handle.and_then(|h| h.port_type.as_ref()),
+            target_handle.and_then(|h| h.port_type.as_ref()),
+        ) && !self
+            .port_compatibility
+            .is_compatible(source_port, target_port)
+        {
+            return Err(ConnectionError::IncompatiblePorts {
+                source: source_port.clone(),
+                target: target_port.clone(),
+            });
+        }
+
+        for (node_id, handle) in [
+            (connection.source_id, source_handle),
+            (connection.target_id, target_handle),
+        ] {
+            if let Some(handle) = handle
+                && handle.multiplicity == Multiplicity::Single
+                && handle_in_use(graph.edges(), node_id, &handle.id)
+            {
+                return Err(ConnectionError::HandleAtCapacity {
+                    node_id,
+                    handle_id: handle.id.clone(),
+                });
+            }
+        }
+
+        if !self.allow_self_loops && connection.source_id == connection.target_id {
+            return Err(ConnectionError::SelfLoop);
+        }
+
+        if !self.allow_duplicates
+            && graph
+                .edges()
+                .iter()
+                .any(|edge| &Connection::from(edge) == connection)
+        {
+            return Err(ConnectionError::Duplicate);
+        }
+
+        if self.prevent_cycles && creates_cycle(graph.edges(), connection) {
+            return Err(ConnectionError::Cycle);
+        }
+
+        if let Some(is_valid_connection) = &self.is_valid_connection
+            && !is_valid_connection(connection, graph)
+        {
+            return Err(ConnectionError::Rejected);
+        }
+
+        Ok(())
+    }
+}
+
+fn find_handle<'a, D>(
+    node: &'a Node<D>,
+    handle_id: &Option<String>,
+) -> Result<Option<&'a Handle>, ConnectionError> {
+    let Some(handle_id) = handle_id else {
+        return Ok(None);
+    };
+    node.handles
+        .iter()
+        .find(|h| &h.id == handle_id)
+        .map(Some)
+        .ok_or_else(|| ConnectionError::MissingHandle {
+            node_id: node.id,
+            handle_id: handle_id.clone(),
+        })
+}
+
+/// Whether any edge is attached to handle `handle_id` of node `node_id`.
+fn handle_in_use(edges: &[Edge], node_id: Uuid, handle_id: &str) -> bool {
+    edges.iter().any(|edge| {
+        (edge.source_id == node_id && edge.source_handle_id.as_deref() == Some(handle_id))
+            || (edge.target_id == node_id && edge.target_handle_id.as_deref() == Some(handle_id))
+    })
+}
+
+/// Whether adding `connection` would close a directed cycle, i.e. whether its
+/// source is already reachable from its target.
+fn creates_cycle(edges: &[Edge], connection: &Connection) -> bool {
+    if connection.source_id == connection.target_id {
+        return true;
+    }
+    let mut outgoing: HashMap<Uuid, Vec<Uuid>> = HashMap::new();
+    for edge in edges {
+        outgoing
+            .entry(edge.source_id)
+            .or_default()
+            .push(edge.target_id);
+    }
+
+    let mut visited = HashSet::new();
+    let mut stack = vec![connection.target_id];
+    while let Some(id) = stack.pop() {
+        if id == connection.source_id {
+            return true;
+        }
+        if visited.insert(id)
+            && let Some(next) = outgoing.get(&id)
+        {
+            stack.extend(next.iter().copied());
+        }
+    }
+    false
+}
+
+/// Why a connection was refused.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConnectionError {
+    MissingNode(Uuid),
+    MissingHandle {
+        node_id: Uuid,
+        handle_id: String,
+    },
+    /// Strict mode requires a source handle on the source end and a target handle on the target end.
+    InvalidHandleType,
+    IncompatiblePorts {
+        source: PortType,
+        target: PortType,
+    },
+    /// A `Multiplicity::Single` handle already has an edge.
+    HandleAtCapacity {
+        node_id: Uuid,
+        handle_id: String,
+    },
+    SelfLoop,
+    Duplicate,
+    Cycle,
+    /// Refused by the user-supplied `is_valid_connection` callback.
+    Rejected,
+}
+
+impl fmt::Display for ConnectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionError::MissingNode(id) => write!(f, "node {id} does not exist"),
+            ConnectionError::MissingHandle { node_id, handle_id } => {
+                write!(f, "node {node_id} has no handle \"{handle_id}\"")
+            }
+            ConnectionError::InvalidHandleType => {
+                write!(f, "edges must connect a source handle to a target handle")
+            }
+            ConnectionError::IncompatiblePorts { source, target } => {
+                write!(f, "cannot connect {} to {}", source.name, target.name)
+            }
+            ConnectionError::HandleAtCapacity { node_id, handle_id } => {
+                write!(
+                    f,
+                    "handle \"{handle_id}\" of node {node_id} is already connected"
+                )
+            }
+            ConnectionError::SelfLoop => write!(f, "self-loops are not allowed"),
+            ConnectionError::Duplicate => write!(f, "an identical edge already exists"),
+            ConnectionError::Cycle => write!(f, "the edge would create a cycle"),
+            ConnectionError::Rejected => write!(f, "the connection was rejected"),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionError {}
+
+impl<D> Graph<D> {
+    /// Adds `edge` if it satisfies `rules`.
+    pub fn try_add_edge(
+        &mut self,
+        edge: Edge,
+        rules: &ConnectionRules<D>,
+    ) -> Result<(), ConnectionError> {
+        rules.validate(&Connection::from(&edge), self)?;
+        self.add_edge(edge);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::position::Position;
+    use gpui::point;
+
+    /// A graph with two nodes, each with an `in` target and an `out` source handle.
+    fn two_nodes(handle: impl Fn(Handle) -> Handle) -> (Graph<()>, Uuid, Uuid) {
+        let mut graph = Graph::new();
+        let mut ids = Vec::new();
+        for _ in 0..2 {
+            let node = Node::new((), point(0.0, 0.0)).with_handles(vec![
+                handle(Handle::new("in", HandleType::Target, Position::Left)),
+                handle(Handle::new("out", HandleType::Source, Position::Right)),
+            ]);
+            ids.push(node.id);
+            graph.add_node(node);
+        }
+        (graph, ids[0], ids[1])
+    }
+
+    fn connection(source_id: Uuid, source: &str, target_id: Uuid, target: &str) -> Connection {
+        Connection {
+            source_id,
+            source_handle_id: Some(source.to_string()),
+            target_id,
+            target_handle_id: Some(target.to_string()),
+        }
+    }
+
+    #[test]
+    fn accepts_source_to_target() {
+        let (graph, a, b) = two_nodes(|h| h);
+        let rules = ConnectionRules::default();
+        assert_eq!(
+            rules.validate(&connection(a, "out", b, "in"), &graph),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn reports_missing_nodes_and_handles() {
+        let (graph, a, _) = two_nodes(|h| h);
+        let rules = ConnectionRules::default();
+        let missing = Uuid::new_v4();
+        assert_eq!(
+            rules.validate(&connection(a, "out", missing, "in"), &graph),
+            Err(ConnectionError::MissingNode(missing))
+        );
+        assert_eq!(
+            rules.validate(&connection(a, "nope", a, "in"), &graph),
+            Err(ConnectionError::MissingHandle {
+                node_id: a,
+                handle_id: "nope".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn strict_mode_checks_handle_types() {
+        let (graph, a, b) = two_nodes(|h| h);
+        let reversed = connection(a, "in", b, "out");
+        assert_eq!(
+            ConnectionRules::default().validate(&reversed, &graph),
+            Err(ConnectionError::InvalidHandleType)
+        );
+        let loose = ConnectionRules::default().with_mode(ConnectionMode::Loose);
+        assert_eq!(loose.validate(&reversed, &graph), Ok(()));
+    }
+
+    #[test]
+    fn port_types_must_be_compatible() {
+        let (mut graph, a, b) = two_nodes(|h| h);
+        graph.get_node_mut(a).unwrap().handles[1].port_type = Some(PortType::float());
+        graph.get_node_mut(b).unwrap().handles[0].port_type = Some(PortType::vec3());
+        let edge = connection(a, "out", b, "in");
+
+        assert_eq!(
+            ConnectionRules::default().validate(&edge, &graph),
+            Err(ConnectionError::IncompatiblePorts {
+                source: PortType::float(),
+                target: PortType::vec3(),
+            })
+        );
+        let coercing = ConnectionRules::default().with_port_compatibility(
+            PortCompatibility::new().with_coercion(&PortType::float(), &PortType::vec3()),
+        );
+        assert_eq!(coercing.validate(&edge, &graph), Ok(()));
+    }
+
+    #[test]
+    fn single_handles_accept_one_edge() {
+        let (mut graph, a, b) = two_nodes(|h| h.with_multiplicity(Multiplicity::Single));
+        let rules = ConnectionRules::default().with_duplicates(true);
+        graph.add_edge(Edge::new(a, b).with_handles("out", "in"));
+        assert_eq!(
+            rules.validate(&connection(a, "out", b, "in"), &graph),
+            Err(ConnectionError::HandleAtCapacity {
+                node_id: a,
+                handle_id: "out".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn self_loops_duplicates_and_cycles() {
+        let (mut graph, a, b) = two_nodes(|h| h);
+        let rules = ConnectionRules::default().with_cycle_prevention(true);
+        assert_eq!(
+            rules.validate(&connection(a, "out", a, "in"), &graph),
+            Err(ConnectionError::SelfLoop)
+        );
+
+        graph.add_edge(Edge::new(a, b).with_handles("out", "in"));
+        assert_eq!(
+            rules.validate(&connection(a, "out", b, "in"), &graph),
+            Err(ConnectionError::Duplicate)
+        );
+        assert_eq!(
+            rules.validate(&connection(b, "out", a, "in"), &graph),
+            Err(ConnectionError::Cycle)
+        );
+        assert_eq!(
+            ConnectionRules::default().validate(&connection(b, "out", a, "in"), &graph),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn validator_runs_last() {
+        let (graph, a, b) = two_nodes(|h| h);
+        let rules = ConnectionRules::default().with_validator(|_, _| false);
+        assert_eq!(
+            rules.validate(&connection(a, "out", b, "in"), &graph),
+            Err(ConnectionError::Rejected)
+        );
+        assert_eq!(
+            rules.validate(&connection(a, "in", b, "out"), &graph),
+            Err(ConnectionError::InvalidHandleType)
+        );
+    }
+
+    #[test]
+    fn permissive_allows_loops_and_duplicates() {
+        let (mut graph, a, b) = two_nodes(|h| h);
+        graph.add_edge(Edge::new(a, b).with_handles("out", "in"));
+        let rules = ConnectionRules::permissive();
+        assert_eq!(
+            rules.validate(&connection(a, "out", b, "in"), &graph),
+            Ok(())
+        );
+        assert_eq!(
+            rules.validate(&connection(a, "in", a, "in"), &graph),
+            Ok(())
+        );
+    }
+}
@@ -1,28 +1,173 @@
-use crate::components::background::{BackgroundProps, render_background};
-use crate::components::edge::render_edge;
-use crate::components::node::render_node;
-use crate::graph::{Graph, Node};
-use crate::types::position::Position;
+use crate::changes::{EdgeChange, NodeChange};
+use crate::components::background::{Background, BackgroundCache, render_background};
+use crate::components::edge::{EdgeStroke, EdgeStyle, render_connection_line, render_edges};
+use crate::components::handle::{Handle, render_handle};
+use crate::components::node::{NodeRenderContext, render_node};
+use crate::components::zoom::zoomed;
+#[cfg(feature = "serde")]
+use crate::document::{DocumentError, FlowDocument};
+use crate::events::GraphEvent;
+use crate::geometry::{
+    edge_endpoints, handle_anchor, handle_bounds, handle_hit_radius, node_bounds,
+};
+use crate::graph::{Connection, Edge, Graph, Node};
+use crate::history::{Command, GraphChange, History};
+use crate::types::handle::HandleType;
+use crate::validation::{ConnectionError, ConnectionRules};
+use crate::viewport::{Transition, Viewport, ViewportAnimation};
 use gpui::*;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 use uuid::Uuid;
 
-type NodeRenderFn<D> = Box<dyn Fn(&Node<D>, &Window) -> AnyElement + Send + Sync>;
+type NodeRenderFn<D> =
+    Box<dyn Fn(&Node<D>, &NodeRenderContext, &Window) -> AnyElement + Send + Sync>;
+type NodesChangeFn<D> = Box<dyn Fn(&mut Vec<NodeChange<D>>, &Graph<D>) + Send + Sync>;
+type EdgesChangeFn<D> = Box<dyn Fn(&mut Vec<EdgeChange>, &Graph<D>) + Send + Sync>;
 
 struct DragState {
     node_id: Uuid,
     offset: Point<f32>,
+    start_position: Point<f32>,
+    /// Whether the pointer has moved since the press; distinguishes clicks from drags.
+    moved: bool,
+}
+
+/// Screen-space margin around the view within which culled elements are still rendered,
+/// so they are ready before they scroll into view.
+const VISIBILITY_MARGIN: f32 = 100.0;
+
+/// Measured sizes closer than this (in flow units) to the stored size are not re-reported.
+const MEASURE_EPSILON: f32 = 0.5;
+
+/// Lowest zoom level `set_zoom_limits` accepts; the view divides by the zoom level.
+const MIN_ZOOM_LIMIT: f32 = 0.01;
+
+/// A connection being dragged out of a handle.
+struct ConnectionState {
+    node_id: Uuid,
+    handle_id: String,
+    handle_type: HandleType,
+    /// Anchor of the originating handle, in flow coordinates.
+    origin: Point<f32>,
+    /// Cursor position, in flow coordinates.
+    cursor: Point<f32>,
+}
+
+/// A box selection being dragged out on the pane, in flow coordinates.
+struct BoxSelection {
+    start: Point<f32>,
+    current: Point<f32>,
+    /// Nodes selected when the box was started with a toggle modifier held; the
+    /// box adds to them and leaves edges alone. `None` when the box replaces the
+    /// selection.
+    base: Option<HashSet<Uuid>>,
+    moved: bool,
+}
+
+impl BoxSelection {
+    fn bounds(&self) -> Bounds<f32> {
+        Bounds::from_corners(
+            point(
+                self.start.x.min(self.current.x),
+                self.start.y.min(self.current.y),
+            ),
+            point(
+                self.start.x.max(self.current.x),
+                self.start.y.max(self.current.y),
+            ),
+        )
+    }
+}
+
+/// Modifier key that turns a drag on the pane into a box selection.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionKey {
+    #[default]
+    Shift,
+    Alt,
+    Control,
+    /// Cmd on macOS, Super/Windows elsewhere.
+    Platform,
+}
+
+impl SelectionKey {
+    fn is_pressed(self, modifiers: &Modifiers) -> bool {
+        match self {
+            SelectionKey::Shift => modifiers.shift,
+            SelectionKey::Alt => modifiers.alt,
+            SelectionKey::Control => modifiers.control,
+            SelectionKey::Platform => modifiers.platform,
+        }
+    }
+
+    /// Whether a box started with this key adds to the selection: a toggle
+    /// modifier (Shift or Cmd) other than this key itself is held.
+    fn is_additive(self, modifiers: &Modifiers) -> bool {
+        (modifiers.shift && self != SelectionKey::Shift)
+            || (modifiers.platform && self != SelectionKey::Platform)
+    }
+}
+
+/// Which nodes a box selection picks up.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SelectionMode {
+    /// Nodes entirely inside the box.
+    #[default]
+    Full,
+    /// Nodes that overlap the box at all.
+    Partial,
+}
+
+/// A camera request that depends on the view's size, made before the first layout.
+enum PendingCamera {
+    Fit {
+        bounds: Bounds<f32>,
+        padding: f32,
+    },
+    Center {
+        center: Point<f32>,
+        zoom: Option<f32>,
+    },
+    Zoom(f32),
 }
 
 pub struct GraphView<D: 'static> {
     graph: Graph<D>,
     drag_state: Option<DragState>,
+    connection_state: Option<ConnectionState>,
+    box_selection: Option<BoxSelection>,
+    /// Modifier that starts a box selection instead of a pan; `None` disables box selection.
+    box_selection_key: Option<SelectionKey>,
+    selection_mode: SelectionMode,
     pan_offset: Point<f32>,
     zoom_level: f32,
+    min_zoom: f32,
+    max_zoom: f32,
+    /// Factor applied to the zoom level per scroll-wheel notch.
+    zoom_step: f32,
+    /// Bounds of the view in window coordinates, captured during the last prepaint.
+    bounds: Rc<Cell<Bounds<Pixels>>>,
+    /// Camera request made before the view had a size, replayed on the first frame.
+    pending_camera: Option<PendingCamera>,
+    viewport_animation: Option<ViewportAnimation>,
     is_panning: bool,
+    pan_moved: bool,
     last_mouse_pos: Point<f32>,
-    background: BackgroundProps,
+    /// Edge under the pointer, drawn highlighted.
+    hovered_edge: Option<Uuid>,
+    /// Width in screen pixels of the band around an edge's curve that accepts pointer input.
+    edge_interaction_width: f32,
+    background: Background,
+    /// Skip nodes and edges outside the visible area when rendering.
+    only_render_visible: bool,
+    background_cache: Rc<RefCell<BackgroundCache>>,
     node_types: HashMap<String, NodeRenderFn<D>>,
+    on_nodes_change: Option<NodesChangeFn<D>>,
+    on_edges_change: Option<EdgesChangeFn<D>>,
+    connection_rules: ConnectionRules<D>,
+    history: History<D>,
 }
 
 impl<D: Clone + Send + Sync + 'static> GraphView<D> {
@@ -30,43 +175,781 @@ impl<D: Clone + Send + Sync + 'static> GraphView<D> {
         let mut node_types: HashMap<String, NodeRenderFn<D>> = HashMap::new();
         node_types.insert(
             "default".to_string(),
-            Box::new(|node, window| render_node(node, window).into_any_element()),
+            Box::new(|node, ctx, window| render_node(node, ctx, window).into_any_element()),
         );
 
         Self {
             graph: Graph::new(),
             drag_state: None,
+            connection_state: None,
+            box_selection: None,
+            box_selection_key: Some(SelectionKey::Shift),
+            selection_mode: SelectionMode::Full,
             pan_offset: Point::default(),
             zoom_level: 1.0,
+            min_zoom: 0.1,
+            max_zoom: 5.0,
+            zoom_step: 1.1,
+            bounds: Rc::new(Cell::new(Bounds::default())),
+            pending_camera: None,
+            viewport_animation: None,
             is_panning: false,
+            pan_moved: false,
             last_mouse_pos: Point::default(),
-            background: BackgroundProps::default(),
+            hovered_edge: None,
+            edge_interaction_width: 20.0,
+            background: Background::default(),
+            only_render_visible: false,
+            background_cache: Rc::default(),
             node_types,
+            on_nodes_change: None,
+            on_edges_change: None,
+            connection_rules: ConnectionRules::default(),
+            history: History::default(),
         }
     }
 
     pub fn register_node_type<F, E>(&mut self, type_name: impl Into<String>, render_fn: F)
     where
-        F: Fn(&Node<D>, &Window) -> E + Send + Sync + 'static,
+        F: Fn(&Node<D>, &NodeRenderContext, &Window) -> E + Send + Sync + 'static,
         E: IntoElement,
     {
         self.node_types.insert(
             type_name.into(),
-            Box::new(move |node, window| render_fn(node, window).into_any_element()),
+            Box::new(move |node, ctx, window| render_fn(node, ctx, window).into_any_element()),
         );
     }
 
-    pub fn set_background(&mut self, props: BackgroundProps, cx: &mut Context<Self>) {
-        self.background = props;
+    /// Registers a handler that sees every node change produced by the view before it
+    /// is applied. The handler may edit, reorder or remove entries to veto them.
+    pub fn on_nodes_change<F>(&mut self, handler: F)
+    where
+        F: Fn(&mut Vec<NodeChange<D>>, &Graph<D>) + Send + Sync + 'static,
+    {
+        self.on_nodes_change = Some(Box::new(handler));
+    }
+
+    /// Registers a handler that sees every edge change produced by the view before it
+    /// is applied. The handler may edit, reorder or remove entries to veto them.
+    pub fn on_edges_change<F>(&mut self, handler: F)
+    where
+        F: Fn(&mut Vec<EdgeChange>, &Graph<D>) + Send + Sync + 'static,
+    {
+        self.on_edges_change = Some(Box::new(handler));
+    }
+
+    /// Runs `changes` through the nodes-change handler and applies what is left.
+    /// Returns `false` if the handler dropped every change.
+    fn dispatch_node_changes(
+        &mut self,
+        mut changes: Vec<NodeChange<D>>,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if let Some(handler) = &self.on_nodes_change {
+            handler(&mut changes, &self.graph);
+        }
+        if changes.is_empty() {
+            return false;
+        }
+        self.graph.apply_node_changes(changes);
         cx.notify();
+        true
     }
 
-    pub fn add_node(&mut self, node: Node<D>) {
+    fn dispatch_edge_changes(&mut self, mut changes: Vec<EdgeChange>, cx: &mut Context<Self>) {
+        if let Some(handler) = &self.on_edges_change {
+            handler(&mut changes, &self.graph);
+        }
+        if !changes.is_empty() {
+            self.graph.apply_edge_changes(changes);
+            cx.notify();
+        }
+    }
+
+    /// Sets the fill and pattern layers. A single `BackgroundProps` becomes one
+    /// layer over the default fill.
+    pub fn set_background(&mut self, background: impl Into<Background>, cx: &mut Context<Self>) {
+        self.background = background.into();
+        cx.notify();
+    }
+
+    pub fn background(&self) -> &Background {
+        &self.background
+    }
+
+    /// Replaces the whole graph and clears the undo history, e.g. to load the
+    /// initial scene.
+    pub fn set_graph(&mut self, graph: Graph<D>, cx: &mut Context<Self>) {
+        self.graph = graph;
+        self.history.clear();
+        self.drag_state = None;
+        self.connection_state = None;
+        self.box_selection = None;
+        self.hovered_edge = None;
+        cx.notify();
+    }
+
+    /// Adds a node, or replaces the node with the same id. The edit is recorded
+    /// in the undo history; build the initial scene with `set_graph` instead.
+    pub fn add_node(&mut self, node: Node<D>, cx: &mut Context<Self>) {
+        self.history.record(match self.graph.get_node(node.id) {
+            Some(before) => Command::ReplaceNode {
+                before: before.clone(),
+                after: node.clone(),
+            },
+            None => Command::AddNode { node: node.clone() },
+        });
         self.graph.add_node(node);
+        cx.notify();
     }
 
-    pub fn add_edge(&mut self, edge: crate::graph::Edge) {
+    /// Adds an edge, or replaces the edge with the same id.
+    pub fn add_edge(&mut self, edge: Edge, cx: &mut Context<Self>) {
+        self.history.record(self.edge_command(&edge));
         self.graph.add_edge(edge);
+        cx.notify();
+    }
+
+    /// Adds `edge` if it satisfies the view's connection rules.
+    pub fn try_add_edge(
+        &mut self,
+        edge: Edge,
+        cx: &mut Context<Self>,
+    ) -> Result<(), ConnectionError> {
+        let command = self.edge_command(&edge);
+        self.graph.try_add_edge(edge, &self.connection_rules)?;
+        self.history.record(command);
+        cx.notify();
+        Ok(())
+    }
+
+    /// The command recording that `edge` is about to be added.
+    fn edge_command(&self, edge: &Edge) -> Command<D> {
+        match self.graph.get_edge(edge.id) {
+            Some(before) => Command::ReplaceEdge {
+                before: before.clone(),
+                after: edge.clone(),
+            },
+            None => Command::AddEdge { edge: edge.clone() },
+        }
+    }
+
+    /// Rules applied to interactively created connections and `try_add_edge`.
+    pub fn set_connection_rules(&mut self, rules: ConnectionRules<D>) {
+        self.connection_rules = rules;
+    }
+
+    pub fn connection_rules(&self) -> &ConnectionRules<D> {
+        &self.connection_rules
+    }
+
+    pub fn remove_node(
+        &mut self,
+        id: Uuid,
+        cx: &mut Context<Self>,
+    ) -> Option<(Node<D>, Vec<Edge>)> {
+        let index = self.graph.node_index(id)?;
+        let edge_indices: Vec<usize> = self
+            .graph
+            .edges()
+            .iter()
+            .enumerate()
+            .filter(|(_, edge)| edge.source_id == id || edge.target_id == id)
+            .map(|(index, _)| index)
+            .collect();
+        let (node, edges) = self.graph.remove_node(id)?;
+        // Edges come back in list order, matching the indices.
+        self.history.record(Command::RemoveNode {
+            index,
+            node: node.clone(),
+            edges: edge_indices
+                .into_iter()
+                .zip(edges.iter().cloned())
+                .collect(),
+        });
+        cx.notify();
+        Some((node, edges))
+    }
+
+    pub fn remove_edge(&mut self, id: Uuid, cx: &mut Context<Self>) -> Option<Edge> {
+        let index = self.graph.edge_index(id)?;
+        let edge = self.graph.remove_edge(id)?;
+        self.history.record(Command::RemoveEdge {
+            index,
+            edge: edge.clone(),
+        });
+        cx.notify();
+        Some(edge)
+    }
+
+    /// Edits a node's data through the nodes-change handler, recording the edit in
+    /// the undo history unless the handler drops it.
+    pub fn update_node_data(
+        &mut self,
+        id: Uuid,
+        update: impl FnOnce(&mut D),
+        cx: &mut Context<Self>,
+    ) {
+        let Some(node) = self.graph.get_node(id) else {
+            return;
+        };
+        let before = node.data.clone();
+        let mut data = before.clone();
+        update(&mut data);
+
+        // Recorded with the data the node ended up with after change handlers ran.
+        if self.dispatch_node_changes(vec![NodeChange::Data { id, data }], cx)
+            && let Some(node) = self.graph.get_node(id)
+        {
+            let after = node.data.clone();
+            self.history
+                .record(Command::EditNodeData { id, before, after });
+        }
+    }
+
+    /// Reverts the last undo step through the change handlers.
+    pub fn undo(&mut self, cx: &mut Context<Self>) {
+        if let Some(command) = self.history.pop_undo() {
+            let changes = command.revert_changes();
+            self.dispatch_changes(changes, cx);
+        }
+    }
+
+    /// Re-applies the last undone step through the change handlers.
+    pub fn redo(&mut self, cx: &mut Context<Self>) {
+        if let Some(command) = self.history.pop_redo() {
+            let changes = command.apply_changes();
+            self.dispatch_changes(changes, cx);
+        }
+    }
+
+    /// Dispatches each run of consecutive node or edge changes in order.
+    fn dispatch_changes(&mut self, changes: Vec<GraphChange<D>>, cx: &mut Context<Self>) {
+        let mut changes = changes.into_iter().peekable();
+        while let Some(change) = changes.next() {
+            match change {
+                GraphChange::Node(change) => {
+                    let mut run = vec![change];
+                    while let Some(GraphChange::Node(change)) =
+                        changes.next_if(|change| matches!(change, GraphChange::Node(_)))
+                    {
+                        run.push(change);
+                    }
+                    self.dispatch_node_changes(run, cx);
+                }
+                GraphChange::Edge(change) => {
+                    let mut run = vec![change];
+                    while let Some(GraphChange::Edge(change)) =
+                        changes.next_if(|change| matches!(change, GraphChange::Edge(_)))
+                    {
+                        run.push(change);
+                    }
+                    self.dispatch_edge_changes(run, cx);
+                }
+            }
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    /// Groups every edit until the matching `commit_transaction` into one undo step.
+    pub fn begin_transaction(&mut self) {
+        self.history.begin_transaction();
+    }
+
+    pub fn commit_transaction(&mut self) {
+        self.history.commit_transaction();
+    }
+
+    /// Sets how many undo steps are kept; older steps are dropped first.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history.set_max_depth(depth);
+    }
+
+    pub fn clear_history(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn graph(&self) -> &Graph<D> {
+        &self.graph
+    }
+
+    /// Limits for the zoom level. The current zoom is clamped into the new range.
+    /// Limits below `0.01` are raised to it.
+    pub fn set_zoom_limits(&mut self, min_zoom: f32, max_zoom: f32, cx: &mut Context<Self>) {
+        let min_zoom = min_zoom.max(MIN_ZOOM_LIMIT);
+        let max_zoom = max_zoom.max(MIN_ZOOM_LIMIT);
+        self.min_zoom = min_zoom.min(max_zoom);
+        self.max_zoom = max_zoom.max(min_zoom);
+        self.zoom_level = self.zoom_level.clamp(self.min_zoom, self.max_zoom);
+        cx.notify();
+    }
+
+    /// Sets the factor the zoom level is multiplied or divided by per scroll-wheel notch.
+    pub fn set_zoom_step(&mut self, zoom_step: f32) {
+        self.zoom_step = zoom_step.max(1.0);
+    }
+
+    /// Ids of the selected nodes, in z-order.
+    pub fn selected_nodes(&self) -> Vec<Uuid> {
+        self.graph
+            .nodes()
+            .iter()
+            .filter(|node| node.selected)
+            .map(|node| node.id)
+            .collect()
+    }
+
+    /// Ids of the selected edges, in insertion order.
+    pub fn selected_edges(&self) -> Vec<Uuid> {
+        self.graph
+            .edges()
+            .iter()
+            .filter(|edge| edge.selected)
+            .map(|edge| edge.id)
+            .collect()
+    }
+
+    /// Selects exactly the given nodes and edges, deselecting everything else.
+    /// Goes through the change handlers and emits `SelectionChanged` like a click.
+    pub fn set_selection(&mut self, nodes: &[Uuid], edges: &[Uuid], cx: &mut Context<Self>) {
+        let nodes: HashSet<Uuid> = nodes.iter().copied().collect();
+        let edges: HashSet<Uuid> = edges.iter().copied().collect();
+        self.update_selection(
+            |node| nodes.contains(&node.id),
+            |edge| edges.contains(&edge.id),
+            cx,
+        );
+    }
+
+    /// Modifier that turns a drag on the pane into a box selection, or `None` to
+    /// always pan. Defaults to Shift. A box replaces the selection, unless Shift or
+    /// Cmd is held on top of the key (e.g. Shift+Cmd with the default key), in which
+    /// case it adds to it.
+    pub fn set_box_selection_key(&mut self, key: Option<SelectionKey>) {
+        self.box_selection_key = key;
+    }
+
+    /// Whether a box selection picks up nodes fully or partially inside it.
+    pub fn set_selection_mode(&mut self, mode: SelectionMode) {
+        self.selection_mode = mode;
+    }
+
+    /// Width in screen pixels of the band around each edge that reacts to hover and
+    /// clicks, independent of the drawn stroke width.
+    pub fn set_edge_interaction_width(&mut self, width: f32) {
+        self.edge_interaction_width = width.max(0.0);
+    }
+
+    /// Whether to render only nodes and edges that intersect the visible area (plus
+    /// a margin). Off by default; worth enabling for large graphs.
+    pub fn set_only_render_visible(&mut self, enabled: bool, cx: &mut Context<Self>) {
+        self.only_render_visible = enabled;
+        cx.notify();
+    }
+
+    pub fn viewport(&self) -> Viewport {
+        Viewport::new(self.pan_offset.x, self.pan_offset.y, self.zoom_level)
+    }
+
+    /// Moves the camera, instantly or over `transition`. The zoom is clamped to the
+    /// view's zoom limits.
+    pub fn set_viewport(
+        &mut self,
+        viewport: Viewport,
+        transition: Option<Transition>,
+        cx: &mut Context<Self>,
+    ) {
+        self.pending_camera = None;
+        let viewport = Viewport {
+            zoom: viewport.zoom.clamp(self.min_zoom, self.max_zoom),
+            ..viewport
+        };
+        match transition {
+            Some(transition) if !transition.duration.is_zero() => {
+                self.viewport_animation = Some(ViewportAnimation::new(
+                    self.viewport(),
+                    viewport,
+                    self.view_center(),
+                    transition,
+                ));
+            }
+            _ => {
+                self.viewport_animation = None;
+                self.apply_viewport(viewport, cx);
+            }
+        }
+        cx.notify();
+    }
+
+    fn apply_viewport(&mut self, viewport: Viewport, cx: &mut Context<Self>) {
+        self.pan_offset = viewport.offset();
+        self.zoom_level = viewport.zoom;
+        self.emit_viewport_changed(cx);
+    }
+
+    /// Zooms and pans so that `nodes` (or every node when `None`) fill the view.
+    ///
+    /// `padding` is a fraction of the view size kept free on each side, e.g. `0.1`.
+    pub fn fit_view(
+        &mut self,
+        padding: f32,
+        nodes: Option<&[Uuid]>,
+        transition: Option<Transition>,
+        cx: &mut Context<Self>,
+    ) {
+        let bounds = match nodes {
+            Some(ids) => ids
+                .iter()
+                .filter_map(|id| self.graph.get_node(*id))
+                .map(node_bounds)
+                .reduce(|a, b| a.union(&b)),
+            None => self
+                .graph
+                .nodes()
+                .iter()
+                .map(node_bounds)
+                .reduce(|a, b| a.union(&b)),
+        };
+        if let Some(bounds) = bounds {
+            self.fit_bounds(bounds, padding, transition, cx);
+        }
+    }
+
+    /// Zooms and pans so that `bounds` (in flow coordinates) fill the view.
+    ///
+    /// Before the view has been laid out the fit is deferred to the first frame.
+    pub fn fit_bounds(
+        &mut self,
+        bounds: Bounds<f32>,
+        padding: f32,
+        transition: Option<Transition>,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.has_size() {
+            self.defer_camera(PendingCamera::Fit { bounds, padding }, cx);
+            return;
+        }
+        let view_size = self.bounds.get().size.map(f32::from);
+
+        let available = size(
+            view_size.width * (1.0 - 2.0 * padding),
+            view_size.height * (1.0 - 2.0 * padding),
+        );
+        let zoom = (available.width / bounds.size.width.max(1.0))
+            .min(available.height / bounds.size.height.max(1.0));
+        self.set_center(bounds.center(), Some(zoom), transition, cx);
+    }
+
+    /// Centres the view on `center` (in flow coordinates), optionally changing the zoom.
+    ///
+    /// Before the view has been laid out this is deferred to the first frame.
+    pub fn set_center(
+        &mut self,
+        center: Point<f32>,
+        zoom: Option<f32>,
+        transition: Option<Transition>,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.has_size() {
+            self.defer_camera(PendingCamera::Center { center, zoom }, cx);
+            return;
+        }
+        let zoom = zoom
+            .unwrap_or(self.zoom_level)
+            .clamp(self.min_zoom, self.max_zoom);
+        let offset = self.view_center() - center * zoom;
+        self.set_viewport(Viewport::new(offset.x, offset.y, zoom), transition, cx);
+    }
+
+    /// Centres the view on a node, optionally changing the zoom.
+    pub fn center_on_node(
+        &mut self,
+        id: Uuid,
+        zoom: Option<f32>,
+        transition: Option<Transition>,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(node) = self.graph.get_node(id) {
+            let center = node_bounds(node).center();
+            self.set_center(center, zoom, transition, cx);
+        }
+    }
+
+    /// Pans so that the flow point `position` is at the view's top-left corner.
+    pub fn pan_to(
+        &mut self,
+        position: Point<f32>,
+        transition: Option<Transition>,
+        cx: &mut Context<Self>,
+    ) {
+        let offset = position * -self.zoom_level;
+        self.set_viewport(
+            Viewport::new(offset.x, offset.y, self.zoom_level),
+            transition,
+            cx,
+        );
+    }
+
+    /// Sets the zoom level, keeping the centre of the view fixed.
+    ///
+    /// Before the view has been laid out this is deferred to the first frame.
+    pub fn zoom_to(&mut self, zoom: f32, transition: Option<Transition>, cx: &mut Context<Self>) {
+        if !self.has_size() {
+            self.defer_camera(PendingCamera::Zoom(zoom), cx);
+            return;
+        }
+        let flow_center = self.viewport().screen_to_flow(self.view_center());
+        self.set_center(flow_center, Some(zoom), transition, cx);
+    }
+
+    pub fn zoom_in(&mut self, transition: Option<Transition>, cx: &mut Context<Self>) {
+        self.zoom_to(self.zoom_level * self.zoom_step, transition, cx);
+    }
+
+    pub fn zoom_out(&mut self, transition: Option<Transition>, cx: &mut Context<Self>) {
+        self.zoom_to(self.zoom_level / self.zoom_step, transition, cx);
+    }
+
+    /// Converts a window position (e.g. from a mouse event) to flow coordinates.
+    pub fn screen_to_flow(&self, position: Point<Pixels>) -> Point<f32> {
+        self.viewport()
+            .screen_to_flow(self.local_position(position))
+    }
+
+    /// Converts a point in flow coordinates to a window position.
+    pub fn flow_to_screen(&self, flow_point: Point<f32>) -> Point<Pixels> {
+        self.viewport().flow_to_screen(flow_point).map(px) + self.bounds.get().origin
+    }
+
+    /// Centre of the view, relative to its top-left corner.
+    fn view_center(&self) -> Point<f32> {
+        let view_size = self.bounds.get().size.map(f32::from);
+        point(view_size.width / 2.0, view_size.height / 2.0)
+    }
+
+    /// Flow-space area to render when culling, or `None` to render everything.
+    fn render_area(&self) -> Option<Bounds<f32>> {
+        let view_size = self.bounds.get().size.map(f32::from);
+        // Before the first layout there is nothing to cull against.
+        if !self.only_render_visible || view_size.width <= 0.0 || view_size.height <= 0.0 {
+            return None;
+        }
+        let screen_area = Bounds::new(
+            point(-VISIBILITY_MARGIN, -VISIBILITY_MARGIN),
+            size(
+                view_size.width + 2.0 * VISIBILITY_MARGIN,
+                view_size.height + 2.0 * VISIBILITY_MARGIN,
+            ),
+        );
+        Some(self.viewport().visible_flow_bounds(screen_area))
+    }
+
+    /// Whether the view has been laid out with a non-empty size.
+    fn has_size(&self) -> bool {
+        let view_size = self.bounds.get().size.map(f32::from);
+        view_size.width > 0.0 && view_size.height > 0.0
+    }
+
+    /// Stores a camera request until the view has a size. It replaces any earlier
+    /// pending request, and any transition is dropped.
+    fn defer_camera(&mut self, camera: PendingCamera, cx: &mut Context<Self>) {
+        self.viewport_animation = None;
+        self.pending_camera = Some(camera);
+        cx.notify();
+    }
+
+    fn apply_pending_camera(&mut self, cx: &mut Context<Self>) {
+        if !self.has_size() {
+            return;
+        }
+        match self.pending_camera.take() {
+            Some(PendingCamera::Fit { bounds, padding }) => {
+                self.fit_bounds(bounds, padding, None, cx)
+            }
+            Some(PendingCamera::Center { center, zoom }) => self.set_center(center, zoom, None, cx),
+            Some(PendingCamera::Zoom(zoom)) => self.zoom_to(zoom, None, cx),
+            None => {}
+        }
+    }
+
+    /// Converts a window position to a position relative to the view's top-left corner.
+    fn local_position(&self, window_position: Point<Pixels>) -> Point<f32> {
+        (window_position - self.bounds.get().origin).map(f32::from)
+    }
+
+    /// Applies a node size measured during layout.
+    fn report_dimensions(&mut self, id: Uuid, size: Size<f32>, cx: &mut Context<Self>) {
+        self.dispatch_node_changes(vec![NodeChange::Dimensions { id, size }], cx);
+    }
+
+    fn emit_viewport_changed(&self, cx: &mut Context<Self>) {
+        cx.emit(GraphEvent::ViewportChanged(self.viewport()));
+    }
+
+    /// Handle closest to `graph_pos` within hit range, with its anchor.
+    fn handle_at(&self, graph_pos: Point<f32>) -> Option<(Uuid, &Handle, Point<f32>)> {
+        self.graph
+            .nearest_handle(graph_pos, handle_hit_radius(self.zoom_level))
+            .map(|(node, handle)| (node.id, handle, handle_anchor(node, handle)))
+    }
+
+    /// Topmost edge within interaction range of `graph_pos`.
+    fn edge_at(&self, graph_pos: Point<f32>) -> Option<Uuid> {
+        let radius = self.edge_interaction_width / 2.0 / self.zoom_level;
+        self.graph.edge_at(graph_pos, radius).map(|edge| edge.id)
+    }
+
+    /// Applies a click on `node`, `edge` or, when both are `None`, the pane. With
+    /// `toggle` the clicked element flips and the rest of the selection is kept;
+    /// otherwise the clicked element becomes the whole selection.
+    fn select_on_click(
+        &mut self,
+        node: Option<Uuid>,
+        edge: Option<Uuid>,
+        toggle: bool,
+        cx: &mut Context<Self>,
+    ) {
+        if toggle {
+            self.update_selection(
+                |n| n.selected != (Some(n.id) == node),
+                |e| e.selected != (Some(e.id) == edge),
+                cx,
+            );
+        } else {
+            self.update_selection(|n| Some(n.id) == node, |e| Some(e.id) == edge, cx);
+        }
+    }
+
+    /// Dispatches `Select` changes for every node and edge whose selection differs
+    /// from the given predicates, then emits `SelectionChanged` if the selection
+    /// changed after change handlers ran.
+    fn update_selection(
+        &mut self,
+        node_selected: impl Fn(&Node<D>) -> bool,
+        edge_selected: impl Fn(&Edge) -> bool,
+        cx: &mut Context<Self>,
+    ) {
+        let (nodes_before, edges_before) = (self.selected_nodes(), self.selected_edges());
+        let node_changes: Vec<NodeChange<D>> = self
+            .graph
+            .nodes()
+            .iter()
+            .filter(|node| node.selected != node_selected(node))
+            .map(|node| NodeChange::Select {
+                id: node.id,
+                selected: !node.selected,
+            })
+            .collect();
+        let edge_changes: Vec<EdgeChange> = self
+            .graph
+            .edges()
+            .iter()
+            .filter(|edge| edge.selected != edge_selected(edge))
+            .map(|edge| EdgeChange::Select {
+                id: edge.id,
+                selected: !edge.selected,
+            })
+            .collect();
+        if !node_changes.is_empty() {
+            self.dispatch_node_changes(node_changes, cx);
+        }
+        if !edge_changes.is_empty() {
+            self.dispatch_edge_changes(edge_changes, cx);
+        }
+
+        let (nodes, edges) = (self.selected_nodes(), self.selected_edges());
+        if nodes != nodes_before || edges != edges_before {
+            cx.emit(GraphEvent::SelectionChanged { nodes, edges });
+        }
+    }
+
+    /// Selects the nodes picked up by the box selection in progress.
+    fn update_box_selection(&mut self, cx: &mut Context<Self>) {
+        let Some(box_selection) = &self.box_selection else {
+            return;
+        };
+        let area = box_selection.bounds();
+        let base = box_selection.base.clone();
+        let selected: HashSet<Uuid> = self
+            .graph
+            .nodes_in_rect(area)
+            .into_iter()
+            .filter(|node| match self.selection_mode {
+                SelectionMode::Full => {
+                    let (min, max) = (node.position, node_bounds(node).bottom_right());
+                    let (area_min, area_max) = (area.origin, area.bottom_right());
+                    min.x >= area_min.x
+                        && min.y >= area_min.y
+                        && max.x <= area_max.x
+                        && max.y <= area_max.y
+                }
+                SelectionMode::Partial => true,
+            })
+            .map(|node| node.id)
+            .collect();
+        let additive = base.is_some();
+        self.update_selection(
+            |node| {
+                selected.contains(&node.id) || base.as_ref().is_some_and(|b| b.contains(&node.id))
+            },
+            |edge| additive && edge.selected,
+            cx,
+        );
+    }
+
+    /// Applies a click on the empty pane at window position `position`.
+    fn click_pane(&mut self, position: Point<Pixels>, toggle: bool, cx: &mut Context<Self>) {
+        self.select_on_click(None, None, toggle, cx);
+        let position = self.local_position(position);
+        cx.emit(GraphEvent::PaneClick {
+            position: (position - self.pan_offset) / self.zoom_level,
+        });
+    }
+
+    /// Finishes a connection drag released over `graph_pos`.
+    fn complete_connection(
+        &mut self,
+        state: ConnectionState,
+        graph_pos: Point<f32>,
+        cx: &mut Context<Self>,
+    ) {
+        let Some((node_id, handle, _)) = self.handle_at(graph_pos) else {
+            return;
+        };
+        if node_id == state.node_id && handle.id == state.handle_id {
+            return;
+        }
+        // Edges run from the source end to the target end, so a drag that started on a
+        // target handle and ended on a source handle is reversed.
+        let edge = if state.handle_type == HandleType::Target
+            && handle.handle_type == HandleType::Source
+        {
+            Edge::new(node_id, state.node_id).with_handles(handle.id.clone(), state.handle_id)
+        } else {
+            Edge::new(state.node_id, node_id).with_handles(state.handle_id, handle.id.clone())
+        };
+        let connection = Connection::from(&edge);
+        if self
+            .connection_rules
+            .validate(&connection, &self.graph)
+            .is_err()
+        {
+            return;
+        }
+        let edge_id = edge.id;
+
+        self.dispatch_edge_changes(vec![EdgeChange::Add { edge }], cx);
+        if let Some(edge) = self.graph.get_edge(edge_id) {
+            self.history.record(Command::AddEdge { edge: edge.clone() });
+        }
+        cx.emit(GraphEvent::Connect(connection));
     }
 
     fn handle_scroll_wheel(
@@ -75,24 +958,28 @@ impl<D: Clone + Send + Sync + 'static> GraphView<D> {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
+        // User input always wins over a programmatic camera move
+        self.viewport_animation = None;
         let delta = event.delta.pixel_delta(px(20.0));
         if delta.y != px(0.0) {
-            let zoom_factor = 1.1f32;
             let new_zoom = if delta.y > px(0.0) {
-                self.zoom_level * zoom_factor
+                self.zoom_level * self.zoom_step
             } else {
-                self.zoom_level / zoom_factor
+                self.zoom_level / self.zoom_step
             };
-            // Clamp zoom
-            self.zoom_level = new_zoom.clamp(0.1, 5.0);
-            cx.notify();
+            // Keep the flow point under the cursor fixed while zooming
+            let cursor = self.local_position(event.position);
+            let flow_point = (cursor - self.pan_offset) / self.zoom_level;
+            self.zoom_level = new_zoom.clamp(self.min_zoom, self.max_zoom);
+            self.pan_offset = cursor - flow_point * self.zoom_level;
         } else {
             // Pan with scroll wheel (touchpad)
             // Convert delta to f32
             let delta_f32 = point(f32::from(delta.x), f32::from(delta.y));
             self.pan_offset += delta_f32;
-            cx.notify();
         }
+        self.emit_viewport_changed(cx);
+        cx.notify();
     }
 
     fn handle_mouse_down(
@@ -101,43 +988,121 @@ impl<D: Clone + Send + Sync + 'static> GraphView<D> {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let position = event.position.map(f32::from);
+        let position = self.local_position(event.position);
         // Transform click position to graph coordinates
         let graph_pos = (position - self.pan_offset) / self.zoom_level;
 
-        // Check if we clicked on a node
-        let mut clicked_node = false;
-        for node in self.graph.nodes.iter().rev() {
-            let node_rect = Bounds::new(node.position, size(150.0, 80.0));
-            if node_rect.contains(&graph_pos) {
-                self.drag_state = Some(DragState {
-                    node_id: node.id,
-                    offset: graph_pos - node.position,
-                });
-                clicked_node = true;
-                cx.notify();
-                break;
-            }
+        // Handles sit on top of their node, so they take priority for starting connections
+        if let Some((node_id, handle, anchor)) = self.handle_at(graph_pos) {
+            self.connection_state = Some(ConnectionState {
+                node_id,
+                handle_id: handle.id.clone(),
+                handle_type: handle.handle_type.clone(),
+                origin: anchor,
+                cursor: graph_pos,
+            });
+            cx.notify();
+            return;
         }
 
-        if !clicked_node {
+        // Check if we clicked on a node
+        let clicked_node = self
+            .graph
+            .node_at(graph_pos)
+            .map(|node| (node.id, graph_pos - node.position));
+
+        let toggle = event.modifiers.shift || event.modifiers.platform;
+        if let Some((node_id, offset)) = clicked_node {
+            self.select_on_click(Some(node_id), None, toggle, cx);
+            self.drag_state = Some(DragState {
+                node_id,
+                offset,
+                start_position: graph_pos - offset,
+                moved: false,
+            });
+            if event.click_count == 2 {
+                cx.emit(GraphEvent::NodeDoubleClick { id: node_id });
+            }
+            cx.notify();
+        } else if let Some(edge_id) = self.edge_at(graph_pos) {
+            self.select_on_click(None, Some(edge_id), toggle, cx);
+            cx.emit(GraphEvent::EdgeClick { id: edge_id });
+            if event.click_count == 2 {
+                cx.emit(GraphEvent::EdgeDoubleClick { id: edge_id });
+            }
+        } else if let Some(key) = self.box_selection_key
+            && key.is_pressed(&event.modifiers)
+        {
+            // The selection only changes once the box is dragged; a click without
+            // movement is a pane click.
+            self.box_selection = Some(BoxSelection {
+                start: graph_pos,
+                current: graph_pos,
+                base: key
+                    .is_additive(&event.modifiers)
+                    .then(|| self.selected_nodes().into_iter().collect()),
+                moved: false,
+            });
+            cx.notify();
+        } else {
+            self.viewport_animation = None;
             self.is_panning = true;
+            self.pan_moved = false;
             self.last_mouse_pos = position;
         }
     }
 
     fn handle_mouse_up(
         &mut self,
-        _event: &MouseUpEvent,
+        event: &MouseUpEvent,
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        if self.drag_state.is_some() {
-            self.drag_state = None;
+        if let Some(connection_state) = self.connection_state.take() {
+            let position = self.local_position(event.position);
+            let graph_pos = (position - self.pan_offset) / self.zoom_level;
+            self.complete_connection(connection_state, graph_pos, cx);
+            cx.notify();
+        }
+        if let Some(drag_state) = self.drag_state.take() {
+            if let Some(node) = self.graph.get_node(drag_state.node_id) {
+                let (id, position) = (node.id, node.position);
+                if drag_state.moved {
+                    let change = NodeChange::Position {
+                        id,
+                        position,
+                        dragging: false,
+                    };
+                    self.dispatch_node_changes(vec![change], cx);
+                    // The whole drag is recorded as a single move, from wherever the
+                    // node ended up after change handlers ran.
+                    if let Some(node) = self.graph.get_node(id)
+                        && node.position != drag_state.start_position
+                    {
+                        self.history.record(Command::MoveNode {
+                            id,
+                            from: drag_state.start_position,
+                            to: node.position,
+                        });
+                    }
+                    cx.emit(GraphEvent::NodeDragStop { id, position });
+                } else {
+                    cx.emit(GraphEvent::NodeClick { id });
+                }
+            }
+            cx.notify();
+        }
+        if let Some(box_selection) = self.box_selection.take() {
+            if !box_selection.moved {
+                self.click_pane(event.position, box_selection.base.is_some(), cx);
+            }
             cx.notify();
         }
         if self.is_panning {
             self.is_panning = false;
+            if !self.pan_moved {
+                self.click_pane(event.position, false, cx);
+            }
             cx.notify();
         }
     }
@@ -148,121 +1113,315 @@ impl<D: Clone + Send + Sync + 'static> GraphView<D> {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) {
-        let position = event.position.map(f32::from);
-        if let Some(drag_state) = &self.drag_state {
+        let position = self.local_position(event.position);
+        if let Some(connection_state) = &mut self.connection_state {
+            connection_state.cursor = (position - self.pan_offset) / self.zoom_level;
+            cx.notify();
+        } else if let Some(drag_state) = &mut self.drag_state {
             let graph_pos = (position - self.pan_offset) / self.zoom_level;
-            if let Some(node) = self
-                .graph
-                .nodes
-                .iter_mut()
-                .find(|n| n.id == drag_state.node_id)
-            {
-                node.position = graph_pos - drag_state.offset;
-                cx.notify();
+            let id = drag_state.node_id;
+            let new_position = graph_pos - drag_state.offset;
+            if !drag_state.moved {
+                drag_state.moved = true;
+                cx.emit(GraphEvent::NodeDragStart {
+                    id,
+                    position: new_position,
+                });
             }
+            let change = NodeChange::Position {
+                id,
+                position: new_position,
+                dragging: true,
+            };
+            self.dispatch_node_changes(vec![change], cx);
+            cx.emit(GraphEvent::NodeDrag {
+                id,
+                position: new_position,
+            });
+        } else if let Some(box_selection) = &mut self.box_selection {
+            box_selection.current = (position - self.pan_offset) / self.zoom_level;
+            box_selection.moved = true;
+            self.update_box_selection(cx);
+            cx.notify();
         } else if self.is_panning {
             let delta = position - self.last_mouse_pos;
             self.pan_offset += delta;
+            self.pan_moved = true;
             self.last_mouse_pos = position;
+            self.emit_viewport_changed(cx);
             cx.notify();
+        } else {
+            let graph_pos = (position - self.pan_offset) / self.zoom_level;
+            // Nodes are drawn over edges, so an edge hidden under a node is not hovered.
+            let hovered_edge = if self.graph.node_at(graph_pos).is_some() {
+                None
+            } else {
+                self.edge_at(graph_pos)
+            };
+            if hovered_edge != self.hovered_edge {
+                self.hovered_edge = hovered_edge;
+                cx.notify();
+            }
         }
     }
 }
 
+#[cfg(feature = "serde")]
+impl<D> GraphView<D>
+where
+    D: Clone + Send + Sync + serde::Serialize + serde::de::DeserializeOwned + 'static,
+{
+    /// Builds a view from a document produced by `to_json`.
+    pub fn from_json(json: &str, cx: &mut Context<Self>) -> Result<Self, DocumentError> {
+        let document = FlowDocument::<D>::from_json(json)?;
+        let mut view = Self::new(cx);
+        view.graph = document.graph;
+        view.pan_offset = document.viewport.offset();
+        view.zoom_level = document.viewport.zoom.clamp(view.min_zoom, view.max_zoom);
+        view.background = document.background;
+        Ok(view)
+    }
+
+    /// Serializes the graph, viewport and background as a versioned JSON document.
+    pub fn to_json(&self) -> Result<String, DocumentError> {
+        FlowDocument::parts_to_json(&self.graph, self.viewport(), &self.background)
+    }
+}
+
+impl<D: 'static> EventEmitter<GraphEvent> for GraphView<D> {}
+
 impl<D: Clone + Send + Sync + 'static> Render for GraphView<D> {
-    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        if let Some(animation) = &self.viewport_animation {
+            let (viewport, finished) = animation.step(self.view_center());
+            self.apply_viewport(viewport, cx);
+            if finished {
+                self.viewport_animation = None;
+            } else {
+                window.request_animation_frame();
+            }
+        }
+
+        let view = cx.entity().downgrade();
+        let (visible_nodes, visible_edges) = match self.render_area() {
+            Some(area) => (
+                self.graph.nodes_in_rect(area),
+                self.graph.edges_in_rect(area),
+            ),
+            None => (
+                self.graph.nodes().iter().collect(),
+                self.graph.edges().iter().collect(),
+            ),
+        };
         div()
             .flex()
             .size_full()
-            .bg(rgb(0x202020))
             .text_color(rgb(0xffffff))
             .relative()
             .on_scroll_wheel(cx.listener(Self::handle_scroll_wheel))
             .on_mouse_down(MouseButton::Left, cx.listener(Self::handle_mouse_down))
             .on_mouse_up(MouseButton::Left, cx.listener(Self::handle_mouse_up))
             .on_mouse_move(cx.listener(Self::handle_mouse_move))
-            .child(render_background(BackgroundProps {
-                variant: self.background.variant,
-                gap: self.background.gap,
-                color: self.background.color,
-                size: self.background.size,
-            }))
+            .child({
+                let bounds = self.bounds.clone();
+                let view = view.clone();
+                let has_pending_camera = self.pending_camera.is_some();
+                canvas(
+                    move |view_bounds, window, _| {
+                        bounds.set(view_bounds);
+                        // Camera requests made before layout need the size captured here.
+                        if has_pending_camera
+                            && view_bounds.size.width > px(0.0)
+                            && view_bounds.size.height > px(0.0)
+                        {
+                            window.on_next_frame(move |_, cx| {
+                                view.update(cx, |this, cx| this.apply_pending_camera(cx))
+                                    .ok();
+                            });
+                        }
+                    },
+                    |_, _, _, _| {},
+                )
+                .absolute()
+                .size_full()
+            })
+            .child(render_background(
+                self.background.clone(),
+                self.viewport(),
+                self.background_cache.clone(),
+            ))
             .child(
                 div()
                     .absolute()
                     .left(px(self.pan_offset.x))
                     .top(px(self.pan_offset.y))
-                    .size_full() // This might be an issue if size is constrained, but for absolute it's ok?
-                    // Actually we want a transform container
-                    // GPUI doesn't have arbitrary transform on div yet easily exposed?
-                    // We can just offset children.
-                    // But scaling?
-                    // We can use `with_transform` or just manual scaling of coordinates.
-                    // Manual scaling is easier for now given we control rendering.
-                    .children(self.graph.edges.iter().filter_map(|edge| {
-                        let source = self.graph.get_node(edge.source_id)?;
-                        let target = self.graph.get_node(edge.target_id)?;
-
-                        // Helper to get handle position relative to node
-                        let get_handle_pos = |node: &Node<D>,
-                                              handle_id: &Option<String>,
-                                              default_pos: Point<f32>|
-                         -> Point<f32> {
-                            if let Some(handle) = handle_id
-                                .as_ref()
-                                .and_then(|h_id| node.handles.iter().find(|h| &h.id == h_id))
-                            {
-                                let (x, y) = match handle.position {
-                                    Position::Top => (75.0, 0.0),
-                                    Position::Bottom => (75.0, 80.0),
-                                    Position::Left => (0.0, 40.0),
-                                    Position::Right => (150.0, 40.0),
-                                };
-                                return (node.position * self.zoom_level)
-                                    + point(x * self.zoom_level, y * self.zoom_level);
-                            }
-                            default_pos
-                        };
-
-                        let source_default = (source.position * self.zoom_level)
-                            + point(150.0 * self.zoom_level / 2.0, 80.0 * self.zoom_level);
-                        let target_default = (target.position * self.zoom_level)
-                            + point(150.0 * self.zoom_level / 2.0, 0.0);
+                    .size_full()
+                    // Scaled manually, see `zoomed`.
+                    .child(render_edges(
+                        visible_edges
+                            .into_iter()
+                            .filter_map(|edge| {
+                                let source = self.graph.get_node(edge.source_id)?;
+                                let target = self.graph.get_node(edge.target_id)?;
+                                let (source_pos, target_pos) = edge_endpoints(edge, source, target);
 
-                        let source_pos =
-                            get_handle_pos(source, &edge.source_handle_id, source_default);
-                        let target_pos =
-                            get_handle_pos(target, &edge.target_handle_id, target_default);
+                                // Edges take the colour of the port type they carry
+                                let style = edge
+                                    .source_handle_id
+                                    .as_ref()
+                                    .and_then(|h_id| source.handles.iter().find(|h| &h.id == h_id))
+                                    .and_then(|h| h.port_type.as_ref())
+                                    .map_or_else(EdgeStyle::default, |port| {
+                                        EdgeStyle::new(port.color)
+                                    });
+                                let style = if edge.selected {
+                                    style.selected()
+                                } else if self.hovered_edge == Some(edge.id) {
+                                    style.hovered()
+                                } else {
+                                    style
+                                };
 
-                        Some(render_edge(edge, source_pos, target_pos, _window))
+                                Some(EdgeStroke {
+                                    source: source_pos * self.zoom_level,
+                                    target: target_pos * self.zoom_level,
+                                    style,
+                                })
+                            })
+                            .collect(),
+                    ))
+                    .children(self.connection_state.as_ref().map(|state| {
+                        render_connection_line(
+                            state.origin * self.zoom_level,
+                            state.cursor * self.zoom_level,
+                        )
                     }))
-                    .children(self.graph.nodes.iter().map(|node| {
+                    .children(visible_nodes.into_iter().map(|node| {
+                        // Axes without an explicit size shrink to the rendered content,
+                        // which is measured and reported back as a Dimensions change.
+                        let mut container = div();
+                        if let Some(width) = node.width {
+                            container = container.w(px(width * self.zoom_level));
+                        }
+                        if let Some(height) = node.height {
+                            container = container.h(px(height * self.zoom_level));
+                        }
+
+                        let render_ctx = NodeRenderContext {
+                            zoom: self.zoom_level,
+                            selected: node.selected,
+                        };
+                        let view = view.clone();
+                        let zoom_level = self.zoom_level;
+                        let node_id = node.id;
+                        let measured = node.measured;
                         div()
                             .absolute()
                             .left(px(node.position.x * self.zoom_level))
                             .top(px(node.position.y * self.zoom_level))
-                            .child(
-                                div()
-                                    .w(px(150.0 * self.zoom_level))
-                                    .h(px(80.0 * self.zoom_level))
-                                    .h(px(80.0 * self.zoom_level))
-                                    .child(
-                                        if let Some(render_fn) =
-                                            self.node_types.get(&node.node_type)
-                                        {
-                                            render_fn(node, _window)
+                            .on_children_prepainted(move |bounds, window, _cx| {
+                                let Some(bounds) = bounds.first() else {
+                                    return;
+                                };
+                                let new_size = size(
+                                    f32::from(bounds.size.width) / zoom_level,
+                                    f32::from(bounds.size.height) / zoom_level,
+                                );
+                                if measured.is_some_and(|m| {
+                                    (m.width - new_size.width).abs() < MEASURE_EPSILON
+                                        && (m.height - new_size.height).abs() < MEASURE_EPSILON
+                                }) {
+                                    return;
+                                }
+                                // The view can't be updated mid-draw, so report on the next frame.
+                                let view = view.clone();
+                                window.on_next_frame(move |_, cx| {
+                                    view.update(cx, |this, cx| {
+                                        this.report_dimensions(node_id, new_size, cx)
+                                    })
+                                    .ok();
+                                });
+                            })
+                            .child(zoomed(
+                                self.zoom_level,
+                                container.child(
+                                    if let Some(render_fn) = self.node_types.get(&node.node_type) {
+                                        render_fn(node, &render_ctx, window)
+                                    } else {
+                                        // Fallback to default if type not found
+                                        if let Some(default_fn) = self.node_types.get("default") {
+                                            default_fn(node, &render_ctx, window)
                                         } else {
-                                            // Fallback to default if type not found
-                                            if let Some(default_fn) = self.node_types.get("default")
-                                            {
-                                                default_fn(node, _window)
-                                            } else {
-                                                div().child("Unknown node type").into_any_element()
-                                            }
-                                        },
+                                            div().child("Unknown node type").into_any_element()
+                                        }
+                                    },
+                                ),
+                            ))
+                            // Handles are drawn by the view rather than by node renderers so
+                            // they sit exactly where edges attach, whatever the node type.
+                            .children(node.handles.iter().map(|handle| {
+                                let flow_bounds = handle_bounds(node, handle);
+                                let origin = (flow_bounds.origin - node.position) * self.zoom_level;
+                                let dot_bounds = Bounds::new(
+                                    origin.map(px),
+                                    size(
+                                        px(flow_bounds.size.width * self.zoom_level),
+                                        px(flow_bounds.size.height * self.zoom_level),
                                     ),
-                            )
+                                );
+                                render_handle(handle, dot_bounds)
+                            }))
                     })),
             )
+            .children(self.box_selection.as_ref().map(|box_selection| {
+                let bounds = box_selection.bounds();
+                let origin = self.viewport().flow_to_screen(bounds.origin);
+                div()
+                    .absolute()
+                    .left(px(origin.x))
+                    .top(px(origin.y))
+                    .w(px(bounds.size.width * self.zoom_level))
+                    .h(px(bounds.size.height * self.zoom_level))
+                    .bg(rgba(0x4a90e233))
+                    .border_1()
+                    .border_color(rgb(0x4a90e2))
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_box_key_replaces_the_selection() {
+        let shift = Modifiers {
+            shift: true,
+            ..Modifiers::default()
+        };
+        let shift_cmd = Modifiers {
+            platform: true,
+            ..shift
+        };
+        let key = SelectionKey::default();
+        assert!(key.is_pressed(&shift));
+        assert!(!key.is_additive(&shift));
+        assert!(key.is_additive(&shift_cmd));
+    }
+
+    #[test]
+    fn toggle_modifiers_make_other_box_keys_additive() {
+        let alt = Modifiers {
+            alt: true,
+            ..Modifiers::default()
+        };
+        let alt_shift = Modifiers { shift: true, ..alt };
+        assert!(!SelectionKey::Alt.is_additive(&alt));
+        assert!(SelectionKey::Alt.is_additive(&alt_shift));
+        let cmd = Modifiers {
+            platform: true,
+            ..Modifiers::default()
+        };
+        assert!(!SelectionKey::Platform.is_additive(&cmd));
     }
 }
@@ -0,0 +1,130 @@
+use gpui::{Bounds, Point, ease_in_out, point};
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+/// Camera of a `GraphView`: the on-screen position of the flow origin and the zoom level.
+///
+/// Screen positions are relative to the view's top-left corner.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Viewport {
+    pub x: f32,
+    pub y: f32,
+    pub zoom: f32,
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            x: 0.0,
+            y: 0.0,
+            zoom: 1.0,
+        }
+    }
+}
+
+impl Viewport {
+    pub fn new(x: f32, y: f32, zoom: f32) -> Self {
+        Self { x, y, zoom }
+    }
+
+    pub fn offset(&self) -> Point<f32> {
+        point(self.x, self.y)
+    }
+
+    pub fn flow_to_screen(&self, flow_point: Point<f32>) -> Point<f32> {
+        flow_point * self.zoom + self.offset()
+    }
+
+    pub fn screen_to_flow(&self, screen_point: Point<f32>) -> Point<f32> {
+        (screen_point - self.offset()) / self.zoom
+    }
+
+    /// The flow-space rectangle visible in a view of the given screen size.
+    pub fn visible_flow_bounds(&self, screen_bounds: Bounds<f32>) -> Bounds<f32> {
+        Bounds::from_corners(
+            self.screen_to_flow(screen_bounds.origin),
+            self.screen_to_flow(screen_bounds.bottom_right()),
+        )
+    }
+}
+
+/// How a programmatic camera move is animated.
+#[derive(Clone)]
+pub struct Transition {
+    pub duration: Duration,
+    /// Maps linear progress in `0.0..=1.0` to eased progress, e.g. `gpui::ease_in_out`.
+    pub easing: Rc<dyn Fn(f32) -> f32>,
+}
+
+impl Transition {
+    pub fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            easing: Rc::new(ease_in_out),
+        }
+    }
+
+    pub fn with_easing(mut self, easing: impl Fn(f32) -> f32 + 'static) -> Self {
+        self.easing = Rc::new(easing);
+        self
+    }
+}
+
+impl fmt::Debug for Transition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Transition")
+            .field("duration", &self.duration)
+            .finish_non_exhaustive()
+    }
+}
+
+/// An in-flight camera transition.
+///
+/// The flow point at the centre of the view and the zoom level are interpolated
+/// (the zoom geometrically), rather than the raw offset, so the camera travels in
+/// a straight line on screen even when the zoom changes.
+pub(crate) struct ViewportAnimation {
+    from_center: Point<f32>,
+    to_center: Point<f32>,
+    from_zoom: f32,
+    to_zoom: f32,
+    start: Instant,
+    transition: Transition,
+}
+
+impl ViewportAnimation {
+    /// Starts animating from `from` to `to` in a view whose centre is at `view_center`.
+    pub(crate) fn new(
+        from: Viewport,
+        to: Viewport,
+        view_center: Point<f32>,
+        transition: Transition,
+    ) -> Self {
+        Self {
+            from_center: from.screen_to_flow(view_center),
+            to_center: to.screen_to_flow(view_center),
+            from_zoom: from.zoom,
+            to_zoom: to.zoom,
+            start: Instant::now(),
+            transition,
+        }
+    }
+
+    /// Viewport at the current time, and whether the transition has finished.
+    pub(crate) fn step(&self, view_center: Point<f32>) -> (Viewport, bool) {
+        let duration = self.transition.duration.as_secs_f32();
+        let progress = if duration > 0.0 {
+            (self.start.elapsed().as_secs_f32() / duration).min(1.0)
+        } else {
+            1.0
+        };
+        let t = (self.transition.easing)(progress);
+
+        let zoom = (self.from_zoom.ln() + (self.to_zoom.ln() - self.from_zoom.ln()) * t).exp();
+        let center = self.from_center + (self.to_center - self.from_center) * t;
+        let offset = view_center - center * zoom;
+        (Viewport::new(offset.x, offset.y, zoom), progress >= 1.0)
+    }
+}